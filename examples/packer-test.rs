@@ -87,7 +87,7 @@ fn main() {
             // Save the result
             //
             let exporter = ImageExporter::export(page, None).unwrap();
-            let mut file = File::create(&format!(
+            let mut file = File::create(format!(
                 "target/output/skyline-multi-packer-output-{}.png",
                 i
             ))
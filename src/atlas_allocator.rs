@@ -0,0 +1,213 @@
+use crate::{
+    packer::{reserved_area, MaxRectsHeuristic, MaxRectsPacker, Packer},
+    rect::Rect,
+    texture_packer_config::TexturePackerConfig,
+};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+/// Handle of an area allocated by an [AtlasAllocator].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct AllocId(u64);
+
+/// Allocator handing out and taking back areas of an atlas at runtime, e.g. for glyph caches.
+///
+/// Areas are placed the same way a [TexturePacker](crate::TexturePacker) using
+/// [MaxRectsPacker] places textures: `texture_padding` and `texture_extrusion` are reserved
+/// around every area and the returned rectangles are offset by `border_padding`. Areas are never
+/// rotated since their pixels are usually uploaded as they are. Freed space is merged with the
+/// free space around it, so large areas can be allocated again once their neighbours are gone.
+pub struct AtlasAllocator {
+    config: TexturePackerConfig,
+    packer: MaxRectsPacker,
+    allocations: HashMap<AllocId, Rect>,
+    next_id: u64,
+    // area reserved by all allocations, including padding and extrusion
+    reserved_area: u64,
+}
+
+impl AtlasAllocator {
+    /// Create an empty allocator for an atlas of `max_width` x `max_height` pixels of `config`,
+    /// plus the border.
+    pub fn new(config: TexturePackerConfig) -> Self {
+        Self::with_heuristic(config, MaxRectsHeuristic::default())
+    }
+
+    /// Create an empty allocator choosing free rectangles with the given `heuristic`.
+    pub fn with_heuristic(config: TexturePackerConfig, heuristic: MaxRectsHeuristic) -> Self {
+        let config = TexturePackerConfig {
+            allow_rotation: false,
+            ..config
+        };
+
+        AtlasAllocator {
+            config,
+            packer: MaxRectsPacker::new(config, heuristic),
+            allocations: HashMap::new(),
+            next_id: 0,
+            reserved_area: 0,
+        }
+    }
+
+    /// Allocate an area of `w` x `h` pixels, returning its handle and its position in the atlas
+    /// or `None` if there is no room left.
+    pub fn allocate(&mut self, w: u32, h: u32) -> Option<(AllocId, Rect)> {
+        if w == 0 || h == 0 {
+            return None;
+        }
+
+        let id = AllocId(self.next_id);
+        let mut rect = self.packer.pack(id, &Rect::new(0, 0, w, h))?.frame;
+        self.next_id += 1;
+        self.reserved_area += u64::from(reserved_area(&self.config, &rect).area());
+
+        rect.x += self.config.border_padding;
+        rect.y += self.config.border_padding;
+        self.allocations.insert(id, rect);
+        Some((id, rect))
+    }
+
+    /// Free the area of `id` so it can be allocated again, returning its position in the atlas.
+    pub fn deallocate(&mut self, id: AllocId) -> Option<Rect> {
+        let rect = self.allocations.remove(&id)?;
+
+        let mut frame = rect;
+        frame.x -= self.config.border_padding;
+        frame.y -= self.config.border_padding;
+        self.reserved_area -= u64::from(reserved_area(&self.config, &frame).area());
+        Packer::<AllocId>::free(&mut self.packer, &frame);
+        Some(rect)
+    }
+
+    /// Position of the area of `id` in the atlas.
+    pub fn get(&self, id: AllocId) -> Option<Rect> {
+        self.allocations.get(&id).copied()
+    }
+
+    /// Number of allocated areas.
+    pub fn len(&self) -> usize {
+        self.allocations.len()
+    }
+
+    /// True if nothing is allocated.
+    pub fn is_empty(&self) -> bool {
+        self.allocations.is_empty()
+    }
+
+    /// Free all areas at once.
+    pub fn clear(&mut self) {
+        *self = Self::with_heuristic(self.config, self.packer.heuristic());
+    }
+
+    /// Allocate all areas anew, largest first, to merge the free space scattered between them.
+    ///
+    /// Returns the handles of the moved areas along with their previous and new position, or
+    /// `None` without changing anything if the areas don't fit the new layout. Handles stay
+    /// valid.
+    pub fn compact(&mut self) -> Option<Vec<(AllocId, Rect, Rect)>> {
+        let mut allocations: Vec<(AllocId, Rect)> = self
+            .allocations
+            .iter()
+            .map(|(&id, &rect)| (id, rect))
+            .collect();
+        allocations.sort_by_key(|&(id, rect)| (Reverse(rect.area()), id.0));
+
+        let mut packer = MaxRectsPacker::new(self.config, self.packer.heuristic());
+        let mut moves = Vec::new();
+        for &(id, from) in &allocations {
+            let mut to = packer.pack(id, &Rect::new(0, 0, from.w, from.h))?.frame;
+            to.x += self.config.border_padding;
+            to.y += self.config.border_padding;
+            if to != from {
+                moves.push((id, from, to));
+            }
+        }
+
+        for &(id, _, to) in &moves {
+            self.allocations.insert(id, to);
+        }
+        self.packer = packer;
+        Some(moves)
+    }
+
+    /// Number of pixels not reserved by any allocation, padding and extrusion included.
+    pub fn free_area(&self) -> u64 {
+        u64::from(self.config.max_width) * u64::from(self.config.max_height) - self.reserved_area
+    }
+
+    /// Width and height of the largest area which can currently be allocated, padding and
+    /// extrusion included.
+    pub fn largest_free_rect(&self) -> Option<(u32, u32)> {
+        self.packer
+            .free_rects()
+            .iter()
+            .max_by_key(|rect| rect.area())
+            .map(|rect| (rect.w, rect.h))
+    }
+
+    /// How scattered the free space is, from `0.0` if all of it forms a single rectangle to
+    /// almost `1.0` if it is split into many small pieces.
+    pub fn fragmentation(&self) -> f32 {
+        let free_area = self.free_area();
+        if free_area == 0 {
+            return 0.0;
+        }
+
+        let largest = self
+            .largest_free_rect()
+            .map_or(0, |(w, h)| u64::from(w) * u64::from(h));
+        1.0 - largest as f32 / free_area as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> TexturePackerConfig {
+        TexturePackerConfig {
+            max_width: 8,
+            max_height: 8,
+            border_padding: 1,
+            texture_padding: 0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn deallocate_coalesces_free_space() {
+        let mut allocator = AtlasAllocator::new(config());
+        let ids: Vec<_> = (0..4)
+            .map(|_| allocator.allocate(4, 4).unwrap().0)
+            .collect();
+        assert_eq!(allocator.get(ids[0]), Some(Rect::new(1, 1, 4, 4)));
+        assert!(allocator.allocate(1, 1).is_none());
+
+        allocator.deallocate(ids[0]).unwrap();
+        allocator.deallocate(ids[3]).unwrap();
+        assert!(allocator.fragmentation() > 0.0);
+        assert!(allocator.allocate(8, 4).is_none());
+
+        allocator.deallocate(ids[1]).unwrap();
+        assert_eq!(allocator.deallocate(ids[1]), None);
+        assert_eq!(allocator.free_area(), 48);
+        assert!(allocator.allocate(8, 4).is_some());
+    }
+
+    #[test]
+    fn padding_is_reserved_around_allocations() {
+        let config = TexturePackerConfig {
+            texture_padding: 2,
+            ..config()
+        };
+        let mut allocator = AtlasAllocator::new(config);
+        assert_eq!(allocator.fragmentation(), 0.0);
+
+        let (_, rect) = allocator.allocate(2, 2).unwrap();
+        assert_eq!(rect, Rect::new(1, 1, 2, 2));
+        assert_eq!(allocator.free_area(), 64 - 16);
+        // the largest free rectangle is 8x4 out of the 48 free pixels
+        assert_eq!(allocator.largest_free_rect(), Some((8, 4)));
+        assert_eq!(allocator.fragmentation(), 1.0 - 32.0 / 48.0);
+    }
+}
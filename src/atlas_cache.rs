@@ -0,0 +1,247 @@
+use crate::{
+    atlas_allocator::{AllocId, AtlasAllocator},
+    frame::{Frame, FrameMove},
+    rect::Rect,
+    texture_packer::{PackError, PackResult},
+    texture_packer_config::TexturePackerConfig,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+/// Changes made to an [AtlasCache] by an insertion.
+#[derive(Clone, Debug)]
+pub struct CacheUpdate<K> {
+    /// Frame of the inserted entry, its pixels need to be uploaded to the atlas.
+    pub inserted: Frame<K>,
+    /// Frames of the least recently used entries evicted to make room, in eviction order.
+    pub evicted: Vec<Frame<K>>,
+}
+
+struct Entry<K> {
+    id: AllocId,
+    frame: Frame<K>,
+    last_used: u64,
+}
+
+/// Atlas keeping the most recently used entries, e.g. glyphs or sprites rendered on demand.
+///
+/// When a new entry doesn't fit, the least recently used entries are evicted until it does.
+/// Entries are placed by an [AtlasAllocator] so the same padding rules apply and frames are never
+/// rotated or trimmed.
+pub struct AtlasCache<K: Clone + Eq + Hash> {
+    config: TexturePackerConfig,
+    allocator: AtlasAllocator,
+    entries: HashMap<K, Entry<K>>,
+    // keys by the time they were last used, oldest first
+    lru: BTreeMap<u64, K>,
+    clock: u64,
+}
+
+impl<K: Clone + Eq + Hash> AtlasCache<K> {
+    /// Create an empty cache for an atlas of `max_width` x `max_height` pixels of `config`, plus
+    /// the border.
+    pub fn new(config: TexturePackerConfig) -> Self {
+        AtlasCache {
+            config,
+            allocator: AtlasAllocator::new(config),
+            entries: HashMap::new(),
+            lru: BTreeMap::new(),
+            clock: 0,
+        }
+    }
+
+    /// Get the frame of `key` and mark it as the most recently used entry.
+    pub fn get(&mut self, key: &K) -> Option<&Frame<K>> {
+        let clock = self.tick();
+        let entry = self.entries.get_mut(key)?;
+        let key = self.lru.remove(&entry.last_used)?;
+        self.lru.insert(clock, key);
+        entry.last_used = clock;
+        Some(&entry.frame)
+    }
+
+    /// Get the frame of `key` without marking it as used.
+    pub fn peek(&self, key: &K) -> Option<&Frame<K>> {
+        self.entries.get(key).map(|entry| &entry.frame)
+    }
+
+    /// Insert an entry of `w` x `h` pixels, evicting the least recently used entries if there is
+    /// no room for it. An entry already cached for `key` is replaced.
+    ///
+    /// Nothing is evicted if the entry is larger than the whole atlas.
+    pub fn insert(&mut self, key: K, w: u32, h: u32) -> PackResult<CacheUpdate<K>> {
+        if w == 0 || h == 0 {
+            return Err(PackError::TextureEmpty);
+        }
+        let extra = self.config.texture_padding + self.config.texture_extrusion * 2;
+        if w + extra > self.config.max_width || h + extra > self.config.max_height {
+            return Err(PackError::TextureTooLargeToFitIntoAtlas);
+        }
+
+        self.remove(&key);
+
+        let mut evicted = Vec::new();
+        let (id, rect) = loop {
+            if let Some(allocation) = self.allocator.allocate(w, h) {
+                break allocation;
+            }
+            match self.evict() {
+                Some(frame) => evicted.push(frame),
+                None => return Err(PackError::TextureTooLargeToFitIntoAtlas),
+            }
+        };
+
+        let frame = Frame {
+            key: key.clone(),
+            frame: rect,
+            rotated: false,
+            trimmed: false,
+            source: Rect::new(0, 0, w, h),
+        };
+        let clock = self.tick();
+        self.lru.insert(clock, key.clone());
+        self.entries.insert(
+            key,
+            Entry {
+                id,
+                frame: frame.clone(),
+                last_used: clock,
+            },
+        );
+
+        Ok(CacheUpdate {
+            inserted: frame,
+            evicted,
+        })
+    }
+
+    /// Remove the entry of `key`, returning its frame.
+    pub fn remove(&mut self, key: &K) -> Option<Frame<K>> {
+        let entry = self.entries.remove(key)?;
+        self.lru.remove(&entry.last_used);
+        self.allocator.deallocate(entry.id);
+        Some(entry.frame)
+    }
+
+    /// Move all entries to merge the free space scattered between them, see
+    /// [AtlasAllocator::compact].
+    ///
+    /// Returns the frames which moved or `None` without changing anything if the entries don't
+    /// fit the new layout. New positions may overlap the previous positions of other frames, so
+    /// the copies should read from a snapshot of the atlas.
+    pub fn compact(&mut self) -> Option<Vec<FrameMove<K>>> {
+        let moves = self.allocator.compact()?;
+
+        let mut keys: HashMap<AllocId, K> = self
+            .entries
+            .iter()
+            .map(|(key, entry)| (entry.id, key.clone()))
+            .collect();
+        let mut frame_moves = Vec::with_capacity(moves.len());
+        for (id, from, to) in moves {
+            if let Some(key) = keys.remove(&id) {
+                if let Some(entry) = self.entries.get_mut(&key) {
+                    entry.frame.frame = to;
+                }
+                frame_moves.push(FrameMove {
+                    key,
+                    from,
+                    to,
+                    rotated: false,
+                });
+            }
+        }
+        Some(frame_moves)
+    }
+
+    /// Number of cached entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// True if nothing is cached.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Remove all entries at once.
+    pub fn clear(&mut self) {
+        self.allocator.clear();
+        self.entries.clear();
+        self.lru.clear();
+    }
+
+    /// The allocator placing the entries, e.g. to check its fragmentation.
+    pub fn allocator(&self) -> &AtlasAllocator {
+        &self.allocator
+    }
+
+    // evict the least recently used entry
+    fn evict(&mut self) -> Option<Frame<K>> {
+        let key = self.lru.values().next()?.clone();
+        self.remove(&key)
+    }
+
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let config = TexturePackerConfig {
+            max_width: 8,
+            max_height: 4,
+            texture_padding: 0,
+            ..Default::default()
+        };
+        let mut cache = AtlasCache::new(config);
+        cache.insert("a", 4, 4).unwrap();
+        let b = cache.insert("b", 4, 4).unwrap().inserted;
+        assert!(cache.get(&"a").is_some());
+
+        let update = cache.insert("c", 4, 4).unwrap();
+        assert_eq!(update.evicted.len(), 1);
+        assert_eq!(update.evicted[0].key, "b");
+        assert_eq!(update.inserted.frame, b.frame);
+        assert!(cache.peek(&"b").is_none());
+
+        // both remaining entries make room
+        assert_eq!(cache.insert("d", 8, 4).unwrap().evicted.len(), 2);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(
+            cache.insert("e", 9, 1).unwrap_err(),
+            PackError::TextureTooLargeToFitIntoAtlas
+        );
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn compact_merges_free_space() {
+        let config = TexturePackerConfig {
+            max_width: 4,
+            max_height: 4,
+            texture_padding: 0,
+            ..Default::default()
+        };
+        let mut cache = AtlasCache::new(config);
+        for key in 0..4 {
+            cache.insert(key, 2, 2).unwrap();
+        }
+        cache.remove(&0);
+        cache.remove(&3);
+        assert_eq!(cache.allocator().largest_free_rect(), Some((2, 2)));
+
+        let moves = cache.compact().unwrap();
+        assert_eq!(moves.len(), 2);
+        for frame_move in &moves {
+            assert_eq!(cache.peek(&frame_move.key).unwrap().frame, frame_move.to);
+        }
+        assert_eq!(cache.allocator().largest_free_rect(), Some((4, 2)));
+    }
+}
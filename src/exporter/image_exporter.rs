@@ -2,6 +2,8 @@ use crate::{
     exporter::{ExportResult, Exporter},
     texture::Texture,
 };
+#[cfg(feature = "rayon")]
+use crate::parallel;
 use image::{DynamicImage, ImageBuffer, Rgba};
 use std::marker::PhantomData;
 use crate::exporter::BackgroundColorSettings;
@@ -18,6 +20,29 @@ impl<T: Texture<Pixel = Rgba<u8>>> ImageExporter<T> {
     pub fn export(texture: &T, background_color: Option<BackgroundColorSettings>) -> ExportResult<DynamicImage> {
         <Self as Exporter<T>>::export(texture, background_color)
     }
+
+    /// Export several textures, e.g. the pages of a [MultiTexturePacker](crate::MultiTexturePacker),
+    /// to images in the same order.
+    pub fn export_all(textures: &[T], background_color: Option<BackgroundColorSettings>) -> ExportResult<Vec<DynamicImage>> {
+        textures
+            .iter()
+            .map(|texture| Self::export(texture, background_color))
+            .collect()
+    }
+
+    /// Same as [export_all](Self::export_all), composing the images concurrently.
+    ///
+    /// See [MultiTexturePacker::par_export_pages](crate::MultiTexturePacker::par_export_pages)
+    /// for exporting the pages of a [MultiTexturePacker](crate::MultiTexturePacker).
+    #[cfg(feature = "rayon")]
+    pub fn par_export_all(textures: &[T], background_color: Option<BackgroundColorSettings>) -> ExportResult<Vec<DynamicImage>>
+    where
+        T: Sync,
+    {
+        parallel::map(textures, |texture| Self::export(texture, background_color))
+            .into_iter()
+            .collect()
+    }
 }
 
 impl<T: Texture<Pixel = Rgba<u8>>> Exporter<T> for ImageExporter<T> {
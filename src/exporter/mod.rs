@@ -9,6 +9,7 @@ mod image_exporter;
 pub type ExportResult<T> = Result<T, String>;
 
 /// Background color settings for sections of the image without regions.
+#[derive(Copy, Clone)]
 pub struct BackgroundColorSettings {
     /// Color to use on exported image for sections that have no image region pixels.
     pub color: Rgba<u8>,
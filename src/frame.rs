@@ -30,3 +30,18 @@ pub struct Frame<K> {
     /// Source texture size before any trimming.
     pub source: Rect,
 }
+
+/// Move of a frame to a new position in the atlas, e.g. by
+/// [TexturePacker::compact](crate::TexturePacker::compact).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FrameMove<K> {
+    /// Key of the moved frame.
+    pub key: K,
+    /// Previous position of the frame.
+    pub from: Rect,
+    /// New position of the frame.
+    pub to: Rect,
+    /// True if the frame was turned during the move, the pixels of `from` then have to be rotated
+    /// 90 degrees clockwise if the frame is rotated now and counter-clockwise otherwise.
+    pub rotated: bool,
+}
@@ -18,7 +18,7 @@ impl ImageImporter {
     }
 }
 
-impl<'a> Importer<&'a Path> for ImageImporter {
+impl Importer<&Path> for ImageImporter {
     type Texture = DynamicImage;
 
     fn import(input: &Path) -> ImportResult<DynamicImage> {
@@ -29,7 +29,7 @@ impl<'a> Importer<&'a Path> for ImageImporter {
     }
 }
 
-impl<'a> Importer<&'a [u8]> for ImageImporter {
+impl Importer<&[u8]> for ImageImporter {
     type Texture = DynamicImage;
 
     fn import(input: &[u8]) -> ImportResult<DynamicImage> {
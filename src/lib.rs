@@ -9,17 +9,33 @@
 )]
 
 pub use crate::{
-    frame::Frame, multi_texture_packer::MultiTexturePacker, rect::Rect,
-    texture_packer::TexturePacker, texture_packer_config::TexturePackerConfig,
+    atlas_allocator::{AllocId, AtlasAllocator},
+    atlas_cache::{AtlasCache, CacheUpdate},
+    frame::{Frame, FrameMove},
+    multi_texture_packer::{MultiTexturePacker, PageOverflow},
+    pack_strategy::{PackStrategy, PageDistribution},
+    packer::{
+        GuillotineFreeRectChoice, GuillotineHeuristic, GuillotineSplitRule, MaxRectsHeuristic,
+        PackerAlgorithm, ShelfChoice, ShelfHeuristic,
+    },
+    rect::Rect,
+    sort_heuristic::SortHeuristic,
+    texture_packer::{PackError, PackResult, TexturePacker},
+    texture_packer_config::{ExtrusionMode, TextureOptions, TexturePackerConfig, TrimMode},
 };
 
 pub mod exporter;
 pub mod importer;
+pub mod packer;
 pub mod texture;
 
+mod atlas_allocator;
+mod atlas_cache;
 mod frame;
 mod multi_texture_packer;
-mod packer;
+mod pack_strategy;
+mod parallel;
 mod rect;
+mod sort_heuristic;
 mod texture_packer;
 mod texture_packer_config;
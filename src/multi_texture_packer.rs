@@ -1,9 +1,43 @@
+#[cfg(feature = "rayon")]
 use crate::{
-    texture::{Pixel, Texture},
-    texture_packer::{PackResult, TexturePacker},
-    texture_packer_config::TexturePackerConfig,
+    exporter::{BackgroundColorSettings, ExportResult, ImageExporter},
+    texture_packer::{par_trim_all_own, par_trim_all_ref},
 };
+use crate::{
+    frame::Frame,
+    pack_strategy::{Layout, PackStrategy},
+    packer::{
+        GuillotineHeuristic, GuillotinePacker, MaxRectsHeuristic, MaxRectsPacker, Packer,
+        PackerFactory, ShelfHeuristic, ShelfPacker, SkylinePacker,
+    },
+    parallel,
+    rect::Rect,
+    sort_heuristic::SortHeuristic,
+    texture::{Pixel, SubTexture, Texture},
+    texture_packer::{
+        trim_all_own, trim_all_ref, trimmed_rect, PackError, PackResult, TexturePacker,
+    },
+    texture_packer_config::{ExtrusionMode, TextureOptions, TexturePackerConfig},
+};
+#[cfg(feature = "rayon")]
+use image::DynamicImage;
+use std::cmp::{max, Reverse};
 use std::hash::Hash;
+use std::rc::Rc;
+
+/// What a [MultiTexturePacker] does with a texture that doesn't fit when no page can be added.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum PageOverflow {
+    /// Fail with `PackError::TooManyPages`.
+    #[default]
+    Error,
+    /// Halve the size of the texture until it fits into one of the pages, failing like
+    /// [Error](Self::Error) if it doesn't fit even at 1x1 pixels. [Frame::source] is scaled
+    /// along with the texture.
+    Downscale,
+    /// Leave the texture out.
+    Skip,
+}
 
 /// Texture packer supporting multiple atlases.
 ///
@@ -11,7 +45,14 @@ use std::hash::Hash;
 /// will still attempt to check each page for available space.
 pub struct MultiTexturePacker<'a, T: 'a + Clone, K: Clone + Eq + Hash> {
     config: TexturePackerConfig,
+    new_packer: PackerFactory<K>,
     pages: Vec<TexturePacker<'a, T, K>>,
+    max_pages: Option<usize>,
+    overflow: PageOverflow,
+    // keys of the textures left out because of `overflow`
+    skipped: Vec<K>,
+    // keys of the textures downscaled because of `overflow`, along with their scale
+    downscaled: Vec<(K, f32)>,
 }
 
 impl<'a, Pix: Pixel, T: Clone + Texture<Pixel = Pix>, K: Clone + Eq + Hash>
@@ -21,6 +62,47 @@ impl<'a, Pix: Pixel, T: Clone + Texture<Pixel = Pix>, K: Clone + Eq + Hash>
     pub fn get_pages(&self) -> &[TexturePacker<'a, T, K>] {
         &self.pages
     }
+
+    /// Number of pages.
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Width and height of the atlas of `page`.
+    pub fn page_dimensions(&self, page: usize) -> Option<(u32, u32)> {
+        self.pages
+            .get(page)
+            .map(|packer| (packer.width(), packer.height()))
+    }
+
+    /// Acquire a frame by its name, along with the index of its page.
+    pub fn get_frame(&self, key: &K) -> Option<(usize, &Frame<K>)> {
+        self.pages
+            .iter()
+            .enumerate()
+            .find_map(|(page, packer)| packer.get_frame(key).map(|frame| (page, frame)))
+    }
+
+    /// Keys of the textures which were left out because no page could be added, see
+    /// [PageOverflow::Skip].
+    pub fn skipped(&self) -> &[K] {
+        &self.skipped
+    }
+
+    /// Keys of the textures which were downscaled because no page could be added, along with
+    /// the scale they were packed at, e.g. `0.25` for a quarter of their size. See
+    /// [PageOverflow::Downscale].
+    pub fn downscaled(&self) -> &[(K, f32)] {
+        &self.downscaled
+    }
+
+    /// Iterate over the frames of all pages along with the index of their page.
+    pub fn frames(&self) -> impl Iterator<Item = (usize, &Frame<K>)> + '_ {
+        self.pages
+            .iter()
+            .enumerate()
+            .flat_map(|(page, packer)| packer.get_frames().values().map(move |frame| (page, frame)))
+    }
 }
 
 impl<'a, Pix: Pixel, T: 'a + Clone + Texture<Pixel = Pix>, K: Clone + Eq + Hash>
@@ -28,47 +110,424 @@ impl<'a, Pix: Pixel, T: 'a + Clone + Texture<Pixel = Pix>, K: Clone + Eq + Hash>
 {
     /// Create a new packer using the skyline packing algorithm.
     pub fn new_skyline(config: TexturePackerConfig) -> Self {
+        Self::with_packer(config, SkylinePacker::new)
+    }
+
+    /// Create a new packer using the MaxRects packing algorithm, choosing free rectangles with
+    /// the given `heuristic`.
+    pub fn new_maxrects(config: TexturePackerConfig, heuristic: MaxRectsHeuristic) -> Self {
+        Self::with_packer(config, move |config| MaxRectsPacker::new(config, heuristic))
+    }
+
+    /// Create a new packer using the guillotine packing algorithm, keeping the free space as
+    /// rectangles produced by axis-aligned cuts.
+    pub fn new_guillotine(config: TexturePackerConfig, heuristic: GuillotineHeuristic) -> Self {
+        Self::with_packer(config, move |config| {
+            GuillotinePacker::new(config, heuristic)
+        })
+    }
+
+    /// Create a new packer using the shelf packing algorithm, placing textures next to each other
+    /// in rows. Best suited for textures of similar height such as font glyphs.
+    pub fn new_shelf(config: TexturePackerConfig, heuristic: ShelfHeuristic) -> Self {
+        Self::with_packer(config, move |config| ShelfPacker::new(config, heuristic))
+    }
+
+    /// Create a new packer using a custom packing algorithm.
+    ///
+    /// `new_packer` is called with `config` to create the [Packer] of every new page, which is
+    /// why a factory is taken rather than a packer.
+    pub fn with_packer<P, F>(config: TexturePackerConfig, new_packer: F) -> Self
+    where
+        P: 'static + Packer<K>,
+        F: 'static + Fn(TexturePackerConfig) -> P,
+    {
+        Self::new(
+            config,
+            Rc::new(move |config| -> Box<dyn Packer<K>> { Box::new(new_packer(config)) }),
+        )
+    }
+
+    /// Pack `textures` with every one of `strategies` and keep the layout using the fewest pages
+    /// and then the smallest total area, taking references of the texture objects.
+    ///
+    /// Returns the packer holding the chosen layout along with the strategy that produced it. On
+    /// a tie the strategy listed first wins. [PackStrategy::all] is a good choice of strategies
+    /// for release builds, strategies using
+    /// [PageDistribution::Balanced](crate::PageDistribution::Balanced) fill all pages about
+    /// equally instead. With the `rayon` feature the strategies are tried concurrently.
+    pub fn pack_best_ref<I>(
+        config: TexturePackerConfig,
+        textures: I,
+        strategies: &[PackStrategy],
+    ) -> PackResult<(Self, PackStrategy)>
+    where
+        I: IntoIterator<Item = (K, &'a T)>,
+    {
+        let batch = trim_all_ref(&config, textures)?;
+        Self::insert_best(config, batch, strategies)
+    }
+
+    /// Pack `textures` with every one of `strategies` and keep the layout using the fewest pages
+    /// and then the smallest total area, taking ownership of the texture objects.
+    ///
+    /// See [pack_best_ref](Self::pack_best_ref) for details.
+    pub fn pack_best_own<I>(
+        config: TexturePackerConfig,
+        textures: I,
+        strategies: &[PackStrategy],
+    ) -> PackResult<(Self, PackStrategy)>
+    where
+        I: IntoIterator<Item = (K, T)>,
+    {
+        let batch = trim_all_own(&config, textures)?;
+        Self::insert_best(config, batch, strategies)
+    }
+
+    fn new(config: TexturePackerConfig, new_packer: PackerFactory<K>) -> Self {
         Self {
             config,
+            new_packer,
             pages: vec![],
+            max_pages: None,
+            overflow: PageOverflow::default(),
+            skipped: Vec::new(),
+            downscaled: Vec::new(),
         }
     }
+
+    fn insert_best(
+        config: TexturePackerConfig,
+        batch: Vec<(K, SubTexture<'a, T>, Rect)>,
+        strategies: &[PackStrategy],
+    ) -> PackResult<(Self, PackStrategy)> {
+        let rects: Vec<Rect> = batch.iter().map(|(_, texture, _)| texture.into()).collect();
+
+        // the layouts may be computed concurrently, the best one is picked in order so the
+        // result doesn't depend on the number of threads
+        let layouts = parallel::map(strategies, |strategy| {
+            if strategy.allow_rotation && !config.allow_rotation {
+                None
+            } else {
+                strategy.layout(&config, &rects)
+            }
+        });
+
+        let mut best: Option<(PackStrategy, Layout)> = None;
+        for (strategy, layout) in strategies.iter().zip(layouts) {
+            if let Some(layout) = layout {
+                let better = match best {
+                    Some((_, ref best)) => layout.is_better_than(best),
+                    None => true,
+                };
+                if better {
+                    best = Some((*strategy, layout));
+                }
+            }
+        }
+        let (strategy, layout) = best.ok_or(PackError::TextureTooLargeToFitIntoAtlas)?;
+
+        let algorithm = strategy.algorithm;
+        let mut packer = Self::new(
+            strategy.config(&config),
+            Rc::new(move |config| algorithm.new_packer(config)),
+        );
+        for _ in 0..layout.page_count {
+            let page = packer.new_page();
+            packer.pages.push(page);
+        }
+
+        let mut batch: Vec<_> = batch.into_iter().map(Some).collect();
+        for (&i, &page) in layout.order.iter().zip(&layout.pages) {
+            if let Some((key, texture, source)) = batch[i].take() {
+                packer.pages[page].insert(key, texture, source)?;
+            }
+        }
+        Ok((packer, strategy))
+    }
+
+    fn new_page(&self) -> TexturePacker<'a, T, K> {
+        TexturePacker::new(self.config, self.new_packer.clone())
+    }
 }
 
 impl<'a, Pix: Pixel, T: 'a + Clone + Texture<Pixel = Pix>, K: Clone + Eq + Hash>
     MultiTexturePacker<'a, T, K>
 {
+    /// Limit the number of pages to `max_pages`, `None` to add pages without limit. `overflow`
+    /// decides what happens to textures which don't fit into any page once the limit is
+    /// reached.
+    ///
+    /// Groups are never downscaled or skipped, they fail with `PackError::TooManyPages`.
+    pub fn set_max_pages(&mut self, max_pages: Option<usize>, overflow: PageOverflow) {
+        self.max_pages = max_pages;
+        self.overflow = overflow;
+    }
+
     /// Pack the `texture` into this packer, taking a reference of the texture object.
     pub fn pack_ref(&mut self, key: K, texture: &'a T) -> PackResult<()> {
-        for packer in &mut self.pages {
-            if packer.can_pack(texture) {
-                return packer.pack_ref(key, texture);
-            }
-        }
-        let mut packer = TexturePacker::new_skyline(self.config);
-        packer.pack_ref(key, texture)?;
-        self.pages.push(packer);
-        Ok(())
+        self.pack_ref_with(key, texture, TextureOptions::default())
     }
 
     /// Pack the `texture` into this packer, taking ownership of the texture object.
     pub fn pack_own(&mut self, key: K, texture: T) -> PackResult<()> {
-        for packer in &mut self.pages {
-            if packer.can_pack(&texture) {
-                return packer.pack_own(key, texture);
+        self.pack_own_with(key, texture, TextureOptions::default())
+    }
+
+    /// Pack the `texture` into this packer with the settings of `options` instead of those of
+    /// the configuration, taking a reference of the texture object.
+    ///
+    /// See [TexturePacker::pack_ref_with] for details.
+    pub fn pack_ref_with(
+        &mut self,
+        key: K,
+        texture: &'a T,
+        options: TextureOptions,
+    ) -> PackResult<()> {
+        let (w, h) = (texture.width(), texture.height());
+        let trimmed = trimmed_rect(&options.apply(&self.config), texture)?;
+        let source = Rect::new(trimmed.x, trimmed.y, w, h);
+        self.insert(key, SubTexture::from_ref(texture, trimmed), source, options)
+    }
+
+    /// Pack the `texture` into this packer with the settings of `options` instead of those of
+    /// the configuration, taking ownership of the texture object.
+    ///
+    /// See [TexturePacker::pack_ref_with] for details.
+    pub fn pack_own_with(&mut self, key: K, texture: T, options: TextureOptions) -> PackResult<()> {
+        let (w, h) = (texture.width(), texture.height());
+        let trimmed = trimmed_rect(&options.apply(&self.config), &texture)?;
+        let source = Rect::new(trimmed.x, trimmed.y, w, h);
+        self.insert(key, SubTexture::new(texture, trimmed), source, options)
+    }
+
+    fn insert(
+        &mut self,
+        key: K,
+        texture: SubTexture<'a, T>,
+        source: Rect,
+        options: TextureOptions,
+    ) -> PackResult<()> {
+        let rect = (&texture).into();
+        if let Some(page) = self
+            .pages
+            .iter_mut()
+            .find(|page| page.can_insert(&rect, &options))
+        {
+            return page.insert_with(key, texture, source, options);
+        }
+        if self.can_add_page() {
+            let mut page = self.new_page();
+            page.insert_with(key, texture, source, options)?;
+            self.pages.push(page);
+            return Ok(());
+        }
+
+        match self.overflow {
+            PageOverflow::Error => Err(PackError::TooManyPages),
+            PageOverflow::Skip => {
+                self.skipped.push(key);
+                Ok(())
+            }
+            PageOverflow::Downscale => {
+                let (mut w, mut h) = (rect.w, rect.h);
+                let mut scale = 1.0;
+                while w > 1 || h > 1 {
+                    w = max(w / 2, 1);
+                    h = max(h / 2, 1);
+                    scale /= 2.0;
+                    let resized = Rect::new(0, 0, w, h);
+                    if let Some(page) = self
+                        .pages
+                        .iter_mut()
+                        .find(|page| page.can_insert(&resized, &options))
+                    {
+                        // scale the source like the texture, the axes separately as the size is
+                        // clamped at one pixel
+                        let scaled = |value: u32, to: u32, from: u32| {
+                            (u64::from(value) * u64::from(to) / u64::from(from)) as u32
+                        };
+                        let source = Rect::new(
+                            scaled(source.x, w, rect.w),
+                            scaled(source.y, h, rect.h),
+                            max(scaled(source.w, w, rect.w), w),
+                            max(scaled(source.h, h, rect.h), h),
+                        );
+                        page.insert_with(key.clone(), texture.resized(w, h), source, options)?;
+                        self.downscaled.push((key, scale));
+                        return Ok(());
+                    }
+                }
+                Err(PackError::TooManyPages)
+            }
+        }
+    }
+
+    /// Lay out the last page anew at the smallest size holding its textures, sorted by `order`,
+    /// usually once all textures are packed. The size honours `power_of_two`, `square` and
+    /// `size_multiple` and is used as the page size even with `force_max_dimensions`.
+    ///
+    /// The page keeps its size if its textures don't fit a new layout. Textures packed later may
+    /// grow the page back up to the maximum size, see
+    /// [TexturePacker::pack_smallest_ref](crate::TexturePacker::pack_smallest_ref).
+    pub fn shrink_last_page(&mut self, order: SortHeuristic) {
+        if let Some(page) = self.pages.last_mut() {
+            let _ = page.insert_smallest(Vec::new(), order);
+        }
+    }
+
+    /// Lay out every page anew at the smallest size holding its textures.
+    ///
+    /// See [shrink_last_page](Self::shrink_last_page) for details.
+    pub fn shrink_all_pages(&mut self, order: SortHeuristic) {
+        for page in &mut self.pages {
+            let _ = page.insert_smallest(Vec::new(), order);
+        }
+    }
+
+    /// Fill the extrusion of the texture of `key` as given by `mode`, on whichever page it was
+    /// packed. Returns false if no texture of `key` is packed.
+    ///
+    /// See [TexturePacker::set_extrusion_mode].
+    pub fn set_extrusion_mode(&mut self, key: &K, mode: ExtrusionMode) -> bool {
+        self.pages
+            .iter_mut()
+            .any(|page| page.set_extrusion_mode(key, mode))
+    }
+
+    fn can_add_page(&self) -> bool {
+        match self.max_pages {
+            Some(max_pages) => self.pages.len() < max_pages,
+            None => true,
+        }
+    }
+
+    /// Pack all `textures` of a group onto the same page, taking references of the texture
+    /// objects. The textures are sorted by `order` and placed on the first page holding all of
+    /// them or on a new page.
+    ///
+    /// Fails without packing anything if the group doesn't fit even on an empty page. Pages
+    /// whose [Packer] doesn't support [boxed_clone](Packer::boxed_clone) are skipped.
+    pub fn pack_group_ref<I>(&mut self, textures: I, order: SortHeuristic) -> PackResult<()>
+    where
+        I: IntoIterator<Item = (K, &'a T)>,
+    {
+        let batch = trim_all_ref(&self.config, textures)?;
+        self.insert_group(batch, order)
+    }
+
+    /// Pack all `textures` of a group onto the same page, taking ownership of the texture
+    /// objects.
+    ///
+    /// See [pack_group_ref](Self::pack_group_ref) for details.
+    pub fn pack_group_own<I>(&mut self, textures: I, order: SortHeuristic) -> PackResult<()>
+    where
+        I: IntoIterator<Item = (K, T)>,
+    {
+        let batch = trim_all_own(&self.config, textures)?;
+        self.insert_group(batch, order)
+    }
+
+    fn insert_group(
+        &mut self,
+        mut batch: Vec<(K, SubTexture<'a, T>, Rect)>,
+        order: SortHeuristic,
+    ) -> PackResult<()> {
+        batch.sort_by_key(|(_, texture, _)| Reverse(order.key(texture.width(), texture.height())));
+        for page in &mut self.pages {
+            match page.insert_group(batch) {
+                Ok(()) => return Ok(()),
+                Err(rest) => batch = rest,
             }
         }
-        let mut packer = TexturePacker::new_skyline(self.config);
-        packer.pack_own(key, texture)?;
-        self.pages.push(packer);
+
+        if !self.can_add_page() {
+            return Err(PackError::TooManyPages);
+        }
+        let mut page = self.new_page();
+        for (key, texture, source) in batch {
+            page.insert(key, texture, source)
+                .map_err(|_| PackError::GroupTooLargeToFitIntoAtlas)?;
+        }
+        self.pages.push(page);
         Ok(())
     }
 }
 
+#[cfg(feature = "rayon")]
+impl<'a, Pix: Pixel, T: 'a + Clone + Texture<Pixel = Pix> + Sync, K: Clone + Eq + Hash>
+    MultiTexturePacker<'a, T, K>
+{
+    /// Same as [pack_best_ref](Self::pack_best_ref), trimming the textures concurrently.
+    pub fn par_pack_best_ref<I>(
+        config: TexturePackerConfig,
+        textures: I,
+        strategies: &[PackStrategy],
+    ) -> PackResult<(Self, PackStrategy)>
+    where
+        I: IntoIterator<Item = (K, &'a T)>,
+    {
+        let batch = par_trim_all_ref(&config, textures)?;
+        Self::insert_best(config, batch, strategies)
+    }
+
+    /// Same as [pack_best_own](Self::pack_best_own), trimming the textures concurrently.
+    pub fn par_pack_best_own<I>(
+        config: TexturePackerConfig,
+        textures: I,
+        strategies: &[PackStrategy],
+    ) -> PackResult<(Self, PackStrategy)>
+    where
+        I: IntoIterator<Item = (K, T)>,
+    {
+        let batch = par_trim_all_own(&config, textures)?;
+        Self::insert_best(config, batch, strategies)
+    }
+
+    /// Same as [pack_group_ref](Self::pack_group_ref), trimming the textures concurrently.
+    pub fn par_pack_group_ref<I>(&mut self, textures: I, order: SortHeuristic) -> PackResult<()>
+    where
+        I: IntoIterator<Item = (K, &'a T)>,
+    {
+        let batch = par_trim_all_ref(&self.config, textures)?;
+        self.insert_group(batch, order)
+    }
+
+    /// Same as [pack_group_own](Self::pack_group_own), trimming the textures concurrently.
+    pub fn par_pack_group_own<I>(&mut self, textures: I, order: SortHeuristic) -> PackResult<()>
+    where
+        I: IntoIterator<Item = (K, T)>,
+    {
+        let batch = par_trim_all_own(&self.config, textures)?;
+        self.insert_group(batch, order)
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<'a, T, K> MultiTexturePacker<'a, T, K>
+where
+    T: 'a + Clone + Texture<Pixel = image::Rgba<u8>> + Sync,
+    K: Clone + Eq + Hash + Sync,
+{
+    /// Export all pages to images in page order, composing them concurrently. Gives the same
+    /// images as exporting every page with [ImageExporter].
+    pub fn par_export_pages(
+        &self,
+        background_color: Option<BackgroundColorSettings>,
+    ) -> ExportResult<Vec<DynamicImage>> {
+        let atlases: Vec<_> = self.pages.iter().map(TexturePacker::atlas).collect();
+        ImageExporter::par_export_all(&atlases, background_color)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{texture::memory_rgba8_texture as mrt, texture_packer::PackError};
+    use crate::{
+        exporter::ImageExporter, pack_strategy::PageDistribution, packer::PackerAlgorithm,
+        texture::memory_rgba8_texture as mrt, texture_packer::PackError,
+        texture_packer_config::TrimMode,
+    };
 
     #[test]
     fn texture_too_small() {
@@ -79,9 +538,16 @@ mod tests {
             border_padding: 0,
             texture_padding: 0,
             texture_extrusion: 0,
+            extrusion_mode: ExtrusionMode::Clamp,
             trim: false,
+            trim_mode: TrimMode::Independent,
+            trim_alpha_threshold: 0,
+            alpha_bleed: 0,
             texture_outlines: false,
             force_max_dimensions: false,
+            power_of_two: false,
+            square: false,
+            size_multiple: 1,
         };
         let mut mtp = MultiTexturePacker::new_skyline(config);
         let texture = mrt::MemoryRGBA8Texture::from_memory(&[0, 0, 0, 0, 0, 0, 0, 0], 2, 1);
@@ -90,4 +556,253 @@ mod tests {
             mtp.pack_own(String::from(""), texture)
         );
     }
+
+    #[test]
+    fn pack_best_prefers_fewer_pages() {
+        let config = TexturePackerConfig {
+            max_width: 6,
+            max_height: 4,
+            texture_padding: 0,
+            ..Default::default()
+        };
+        let a = mrt::MemoryRGBA8Texture::from_memory(&[255; 4 * 16], 4, 4);
+        let b = mrt::MemoryRGBA8Texture::from_memory(&[255; 4 * 8], 4, 2);
+        let textures = vec![("a", &a), ("b", &b)];
+        let strategy = |allow_rotation| PackStrategy {
+            algorithm: PackerAlgorithm::Skyline,
+            allow_rotation,
+            order: SortHeuristic::Area,
+            distribution: PageDistribution::FirstFit,
+        };
+        let strategies = [strategy(false), strategy(true)];
+
+        let (packer, best) =
+            MultiTexturePacker::pack_best_ref(config, textures.clone(), &strategies).unwrap();
+        assert_eq!(best, strategy(true));
+        assert_eq!(packer.get_pages().len(), 1);
+        assert!(packer.get_pages()[0].get_frame(&"b").unwrap().rotated);
+
+        let config = TexturePackerConfig {
+            allow_rotation: false,
+            ..config
+        };
+        let (packer, best) =
+            MultiTexturePacker::pack_best_ref(config, textures, &PackStrategy::all()).unwrap();
+        assert!(!best.allow_rotation);
+        assert_eq!(packer.get_pages().len(), 2);
+    }
+
+    #[test]
+    fn export_all_pages() {
+        let config = TexturePackerConfig {
+            max_width: 4,
+            max_height: 4,
+            texture_padding: 0,
+            ..Default::default()
+        };
+        let textures: Vec<_> = (0..3u8)
+            .map(|i| {
+                (
+                    i,
+                    image::RgbaImage::from_pixel(4, 4, image::Rgba([i, i, i, 255])),
+                )
+            })
+            .collect();
+        let (packer, _) =
+            MultiTexturePacker::pack_best_own(config, textures, &PackStrategy::all()).unwrap();
+
+        let pages = packer.get_pages();
+        let images = ImageExporter::export_all(pages, None).unwrap();
+        assert_eq!(images.len(), 3);
+        for (page, image) in pages.iter().zip(&images) {
+            let key = *page.get_frames().keys().next().unwrap();
+            assert_eq!(image.to_rgba8().get_pixel(0, 0).0, [key, key, key, 255]);
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn par_export_matches_export() {
+        let config = TexturePackerConfig {
+            max_width: 4,
+            max_height: 4,
+            texture_padding: 0,
+            ..Default::default()
+        };
+        let textures: Vec<_> = (0..3u8)
+            .map(|i| {
+                (
+                    i,
+                    image::RgbaImage::from_pixel(3, 2, image::Rgba([i, 0, 0, 255])),
+                )
+            })
+            .collect();
+        let (packer, _) =
+            MultiTexturePacker::par_pack_best_own(config, textures, &PackStrategy::all()).unwrap();
+
+        let images = ImageExporter::export_all(packer.get_pages(), None).unwrap();
+        let par_images = packer.par_export_pages(None).unwrap();
+        assert_eq!(par_images, images);
+    }
+
+    #[test]
+    fn groups_share_a_page() {
+        let config = TexturePackerConfig {
+            max_width: 4,
+            max_height: 4,
+            allow_rotation: false,
+            texture_padding: 0,
+            trim: false,
+            ..Default::default()
+        };
+        let tall = mrt::MemoryRGBA8Texture::from_memory(&[255; 4 * 8], 2, 4);
+        let small = mrt::MemoryRGBA8Texture::from_memory(&[255; 4 * 4], 2, 2);
+        let wide = mrt::MemoryRGBA8Texture::from_memory(&[255; 4 * 8], 4, 2);
+
+        let mut mtp = MultiTexturePacker::new_skyline(config);
+        mtp.pack_ref("tall", &tall).unwrap();
+        mtp.pack_group_ref(vec![("s1", &small), ("s2", &small)], SortHeuristic::Area)
+            .unwrap();
+        assert_eq!(mtp.get_pages().len(), 1);
+
+        mtp.pack_group_ref(vec![("w1", &wide), ("w2", &wide)], SortHeuristic::Area)
+            .unwrap();
+        assert_eq!(mtp.get_pages().len(), 2);
+        assert!(mtp.get_pages()[1].get_frame(&"w2").is_some());
+
+        let group = vec![("a", &wide), ("b", &wide), ("c", &wide)];
+        assert_eq!(
+            mtp.pack_group_ref(group, SortHeuristic::Area),
+            Err(PackError::GroupTooLargeToFitIntoAtlas)
+        );
+        assert_eq!(mtp.get_pages().len(), 2);
+    }
+
+    #[test]
+    fn frames_across_pages() {
+        let config = TexturePackerConfig {
+            max_width: 4,
+            max_height: 4,
+            texture_padding: 0,
+            trim: false,
+            ..Default::default()
+        };
+        let big = mrt::MemoryRGBA8Texture::from_memory(&[255; 4 * 16], 4, 4);
+        let small = mrt::MemoryRGBA8Texture::from_memory(&[255; 4 * 2], 2, 1);
+
+        let mut mtp = MultiTexturePacker::new_skyline(config);
+        mtp.pack_ref("a", &big).unwrap();
+        mtp.pack_ref("b", &small).unwrap();
+
+        assert_eq!(mtp.page_count(), 2);
+        assert_eq!(mtp.page_dimensions(0), Some((4, 4)));
+        assert_eq!(mtp.page_dimensions(1), Some((2, 1)));
+        assert_eq!(mtp.page_dimensions(2), None);
+        assert_eq!(mtp.get_frame(&"b").unwrap().0, 1);
+        assert!(mtp.get_frame(&"c").is_none());
+
+        let mut frames: Vec<_> = mtp
+            .frames()
+            .map(|(page, frame)| (page, frame.key))
+            .collect();
+        frames.sort();
+        assert_eq!(frames, vec![(0, "a"), (1, "b")]);
+    }
+
+    #[test]
+    fn page_limit_overflow() {
+        let config = TexturePackerConfig {
+            max_width: 4,
+            max_height: 4,
+            allow_rotation: false,
+            texture_padding: 0,
+            trim: false,
+            ..Default::default()
+        };
+        let wide = mrt::MemoryRGBA8Texture::from_memory(&[255; 4 * 8], 4, 2);
+        let big = mrt::MemoryRGBA8Texture::from_memory(&[255; 4 * 16], 4, 4);
+
+        let packer = |overflow| {
+            let mut mtp = MultiTexturePacker::new_skyline(config);
+            mtp.set_max_pages(Some(1), overflow);
+            mtp.pack_ref("wide", &wide).unwrap();
+            mtp
+        };
+
+        let mut mtp = packer(PageOverflow::Error);
+        assert_eq!(mtp.pack_ref("big", &big), Err(PackError::TooManyPages));
+
+        let mut mtp = packer(PageOverflow::Skip);
+        mtp.pack_ref("big", &big).unwrap();
+        assert_eq!(mtp.skipped(), &["big"]);
+        assert!(mtp.downscaled().is_empty());
+        assert!(mtp.get_frame(&"big").is_none());
+
+        let mut mtp = packer(PageOverflow::Downscale);
+        mtp.pack_ref("big", &big).unwrap();
+        assert!(mtp.skipped().is_empty());
+        assert_eq!(mtp.downscaled(), &[("big", 0.5)]);
+        let (page, frame) = mtp.get_frame(&"big").unwrap();
+        assert_eq!((page, frame.frame), (0, Rect::new(0, 2, 2, 2)));
+        assert_eq!(frame.source, Rect::new(0, 0, 2, 2));
+        assert_eq!(mtp.page_count(), 1);
+    }
+
+    #[test]
+    fn shrink_last_page() {
+        let config = TexturePackerConfig {
+            max_width: 8,
+            max_height: 8,
+            allow_rotation: false,
+            texture_padding: 0,
+            trim: false,
+            force_max_dimensions: true,
+            power_of_two: true,
+            ..Default::default()
+        };
+        let big = mrt::MemoryRGBA8Texture::from_memory(&[255; 4 * 64], 8, 8);
+        let small = mrt::MemoryRGBA8Texture::from_memory(&[255; 4 * 6], 3, 2);
+
+        let mut mtp = MultiTexturePacker::new_skyline(config);
+        mtp.pack_ref("big", &big).unwrap();
+        mtp.pack_ref("small", &small).unwrap();
+        assert_eq!(mtp.page_dimensions(1), Some((8, 8)));
+
+        mtp.shrink_last_page(SortHeuristic::Area);
+        assert_eq!(mtp.page_dimensions(0), Some((8, 8)));
+        assert_eq!(mtp.page_dimensions(1), Some((4, 2)));
+        assert!(mtp.get_frame(&"small").is_some());
+    }
+
+    #[test]
+    fn balanced_distribution() {
+        let config = TexturePackerConfig {
+            max_width: 4,
+            max_height: 4,
+            allow_rotation: false,
+            texture_padding: 0,
+            trim: false,
+            ..Default::default()
+        };
+        let wide = mrt::MemoryRGBA8Texture::from_memory(&[255; 4 * 8], 4, 2);
+        let small = mrt::MemoryRGBA8Texture::from_memory(&[255; 4 * 4], 2, 2);
+        let textures = vec![(0, &wide), (1, &wide), (2, &wide), (3, &wide), (4, &small)];
+
+        let fill = |distribution| {
+            let strategy = PackStrategy {
+                distribution,
+                ..Default::default()
+            };
+            let (mtp, _) =
+                MultiTexturePacker::pack_best_ref(config, textures.clone(), &[strategy]).unwrap();
+            let mut fill = vec![0; mtp.page_count()];
+            for (page, frame) in mtp.frames() {
+                fill[page] += frame.frame.area();
+            }
+            fill
+        };
+
+        assert_eq!(fill(PageDistribution::FirstFit), vec![16, 16, 4]);
+        assert_eq!(fill(PageDistribution::Balanced), vec![16, 12, 8]);
+    }
 }
@@ -0,0 +1,212 @@
+use crate::{
+    packer::{
+        GuillotineFreeRectChoice, GuillotineHeuristic, GuillotineSplitRule, MaxRectsHeuristic,
+        Packer, PackerAlgorithm, ShelfChoice, ShelfHeuristic,
+    },
+    rect::Rect,
+    sort_heuristic::SortHeuristic,
+    texture_packer_config::TexturePackerConfig,
+};
+use std::cmp::{max, Reverse};
+
+/// How a batch of textures is spread over the pages of a
+/// [MultiTexturePacker](crate::MultiTexturePacker).
+///
+/// Only [MultiTexturePacker::pack_best_ref](crate::MultiTexturePacker::pack_best_ref) and
+/// [pack_best_own](crate::MultiTexturePacker::pack_best_own) distribute pages this way, through
+/// [PackStrategy::distribution]. Balancing needs the whole batch up front to know how many pages
+/// to fill, so textures packed one at a time, e.g. by
+/// [pack_own](crate::MultiTexturePacker::pack_own), always go to the first page with room.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum PageDistribution {
+    /// Put every texture on the first page with room for it. Early pages end up full and the
+    /// last one is often sparse.
+    #[default]
+    FirstFit,
+    /// Use as many pages as [FirstFit](Self::FirstFit) and put every texture on the least
+    /// filled page with room for it, so that all pages are filled about equally. Falls back to
+    /// first fit if the textures don't fit that way.
+    Balanced,
+}
+
+/// A way to lay out a batch of textures, as tried by
+/// [MultiTexturePacker::pack_best_own](crate::MultiTexturePacker::pack_best_own).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct PackStrategy {
+    /// Algorithm placing the textures.
+    pub algorithm: PackerAlgorithm,
+    /// True to allow rotation of the textures. Strategies allowing rotation are skipped if
+    /// rotation isn't allowed by the configuration.
+    pub allow_rotation: bool,
+    /// Order in which the textures are packed.
+    pub order: SortHeuristic,
+    /// How the textures are spread over the pages.
+    pub distribution: PageDistribution,
+}
+
+impl PackStrategy {
+    /// A broad selection of strategies, combining every built-in algorithm using a few of its
+    /// heuristics with rotation on and off and every order except [SortHeuristic::None].
+    ///
+    /// All of them use [PageDistribution::FirstFit], which needs the least area.
+    pub fn all() -> Vec<PackStrategy> {
+        let mut algorithms = vec![PackerAlgorithm::Skyline];
+        for heuristic in [
+            MaxRectsHeuristic::BestShortSideFit,
+            MaxRectsHeuristic::BestLongSideFit,
+            MaxRectsHeuristic::BestAreaFit,
+            MaxRectsHeuristic::BottomLeftRule,
+            MaxRectsHeuristic::ContactPointRule,
+        ] {
+            algorithms.push(PackerAlgorithm::MaxRects(heuristic));
+        }
+        for choice in [
+            GuillotineFreeRectChoice::BestAreaFit,
+            GuillotineFreeRectChoice::BestShortSideFit,
+        ] {
+            for split in [
+                GuillotineSplitRule::ShorterLeftoverAxis,
+                GuillotineSplitRule::MinimizeArea,
+            ] {
+                algorithms.push(PackerAlgorithm::Guillotine(GuillotineHeuristic {
+                    choice,
+                    split,
+                    merge: true,
+                }));
+            }
+        }
+        algorithms.push(PackerAlgorithm::Shelf(ShelfHeuristic {
+            choice: ShelfChoice::BestHeightFit,
+            waste_map: true,
+        }));
+
+        let mut strategies = Vec::new();
+        for &algorithm in &algorithms {
+            for allow_rotation in [true, false] {
+                for order in [
+                    SortHeuristic::Area,
+                    SortHeuristic::MaxSide,
+                    SortHeuristic::Perimeter,
+                    SortHeuristic::Height,
+                    SortHeuristic::Width,
+                ] {
+                    strategies.push(PackStrategy {
+                        algorithm,
+                        allow_rotation,
+                        order,
+                        distribution: PageDistribution::FirstFit,
+                    });
+                }
+            }
+        }
+        strategies
+    }
+
+    // `config` with the rotation setting of this strategy
+    pub(crate) fn config(&self, config: &TexturePackerConfig) -> TexturePackerConfig {
+        TexturePackerConfig {
+            allow_rotation: self.allow_rotation,
+            ..config.constrained()
+        }
+    }
+
+    // lay out `rects` the way `MultiTexturePacker` does, `None` if one of them doesn't even fit
+    // on an empty page
+    pub(crate) fn layout(&self, config: &TexturePackerConfig, rects: &[Rect]) -> Option<Layout> {
+        let config = self.config(config);
+
+        let mut order: Vec<usize> = (0..rects.len()).collect();
+        order.sort_by_key(|&i| Reverse(self.order.key(rects[i].w, rects[i].h)));
+
+        let first_fit = self.distribute(&config, rects, order, 0)?;
+        match self.distribution {
+            PageDistribution::FirstFit => Some(first_fit),
+            PageDistribution::Balanced => {
+                let pages = first_fit.page_count;
+                match self.distribute(&config, rects, first_fit.order.clone(), pages) {
+                    Some(balanced) if balanced.page_count == pages => Some(balanced),
+                    _ => Some(first_fit),
+                }
+            }
+        }
+    }
+
+    // put `rects` in this `order` on pages, either the first page with room or with
+    // `PageDistribution::Balanced` the least filled one among `pages` opened beforehand
+    fn distribute(
+        &self,
+        config: &TexturePackerConfig,
+        rects: &[Rect],
+        order: Vec<usize>,
+        pages: usize,
+    ) -> Option<Layout> {
+        let border = config.border_padding * 2;
+        let balanced = pages > 0;
+
+        let mut packers: Vec<Box<dyn Packer<usize>>> = (0..pages)
+            .map(|_| self.algorithm.new_packer(*config))
+            .collect();
+        let mut used = vec![(0, 0); pages];
+        let mut filled = vec![0u64; pages];
+        let mut page_of = Vec::with_capacity(order.len());
+        for &i in &order {
+            let rect = &rects[i];
+            let mut fitting = (0..packers.len()).filter(|&page| packers[page].can_pack(rect));
+            let page = if balanced {
+                fitting.min_by_key(|&page| filled[page])
+            } else {
+                fitting.next()
+            };
+            let page = match page {
+                Some(page) => page,
+                None => {
+                    packers.push(self.algorithm.new_packer(*config));
+                    used.push((0, 0));
+                    filled.push(0);
+                    packers.len() - 1
+                }
+            };
+
+            let frame = packers[page].pack(i, rect)?.frame;
+            let (w, h) = used[page];
+            used[page] = (
+                max(w, frame.x + frame.w + border),
+                max(h, frame.y + frame.h + border),
+            );
+            filled[page] += u64::from(rect.area());
+            page_of.push(page);
+        }
+
+        let area = used
+            .iter()
+            .map(|&(w, h)| {
+                let (w, h) = config.atlas_size(w, h);
+                u64::from(w) * u64::from(h)
+            })
+            .sum();
+
+        Some(Layout {
+            order,
+            pages: page_of,
+            page_count: packers.len(),
+            area,
+        })
+    }
+}
+
+// a batch of textures laid out by a strategy
+pub(crate) struct Layout {
+    // indices of the textures in packing order
+    pub order: Vec<usize>,
+    // page of every texture, in packing order
+    pub pages: Vec<usize>,
+    pub page_count: usize,
+    // total area of all pages
+    pub area: u64,
+}
+
+impl Layout {
+    pub(crate) fn is_better_than(&self, other: &Layout) -> bool {
+        (self.page_count, self.area) < (other.page_count, other.area)
+    }
+}
@@ -0,0 +1,454 @@
+use crate::{
+    frame::Frame,
+    packer::{overlaps, reserved_area, Packer},
+    rect::Rect,
+    texture_packer_config::TexturePackerConfig,
+};
+use std::cmp::{max, min};
+
+/// Rule used by the guillotine packer to choose the free rectangle a texture is placed into.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum GuillotineFreeRectChoice {
+    /// Choose the smallest free rectangle the texture fits into.
+    #[default]
+    BestAreaFit,
+    /// Choose the free rectangle where the shorter leftover side is minimal.
+    BestShortSideFit,
+    /// Choose the free rectangle where the longer leftover side is minimal.
+    BestLongSideFit,
+    /// Choose the largest free rectangle the texture fits into.
+    WorstAreaFit,
+    /// Choose the free rectangle where the shorter leftover side is maximal.
+    WorstShortSideFit,
+    /// Choose the free rectangle where the longer leftover side is maximal.
+    WorstLongSideFit,
+}
+
+/// Rule used by the guillotine packer to cut the space left over in a free rectangle after a
+/// texture was placed into it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum GuillotineSplitRule {
+    /// Cut along the shorter leftover axis.
+    #[default]
+    ShorterLeftoverAxis,
+    /// Cut along the longer leftover axis.
+    LongerLeftoverAxis,
+    /// Cut so that the smaller of the two resulting free rectangles is as small as possible.
+    MinimizeArea,
+    /// Cut so that the larger of the two resulting free rectangles is as large as possible.
+    MaximizeArea,
+    /// Cut along the shorter axis of the free rectangle.
+    ShorterAxis,
+    /// Cut along the longer axis of the free rectangle.
+    LongerAxis,
+}
+
+/// Settings of the guillotine packing algorithm.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct GuillotineHeuristic {
+    /// Rule to choose the free rectangle a texture is placed into.
+    pub choice: GuillotineFreeRectChoice,
+    /// Rule to cut the leftover space of the chosen free rectangle.
+    pub split: GuillotineSplitRule,
+    /// True to merge adjacent free rectangles of the same size after each placement. Default
+    /// value is `false`.
+    pub merge: bool,
+}
+
+/// Packer keeping its free space as disjoint rectangles produced by guillotine cuts.
+#[derive(Clone)]
+pub struct GuillotinePacker {
+    config: TexturePackerConfig,
+    heuristic: GuillotineHeuristic,
+
+    // disjoint free rectangles, each one was produced by a guillotine cut
+    free_rects: Vec<Rect>,
+}
+
+impl GuillotinePacker {
+    /// Create an empty guillotine packer covering `max_width` x `max_height` of `config`.
+    pub fn new(config: TexturePackerConfig, heuristic: GuillotineHeuristic) -> Self {
+        let free_rects = if config.max_width > 0 && config.max_height > 0 {
+            vec![Rect::new(0, 0, config.max_width, config.max_height)]
+        } else {
+            Vec::new()
+        };
+
+        GuillotinePacker {
+            config,
+            heuristic,
+            free_rects,
+        }
+    }
+
+    // a packer without any free space, to be filled with `add_free_rect`
+    pub(super) fn empty(config: TexturePackerConfig, heuristic: GuillotineHeuristic) -> Self {
+        GuillotinePacker {
+            config,
+            heuristic,
+            free_rects: Vec::new(),
+        }
+    }
+
+    // `rect` must not overlap any other free rectangle or placed texture
+    pub(super) fn add_free_rect(&mut self, rect: Rect) {
+        if rect.w > 0 && rect.h > 0 {
+            self.free_rects.push(rect);
+            if self.heuristic.merge {
+                self.merge();
+            }
+        }
+    }
+
+    // score of putting a rectangle (w, h) into `free`, lower is better
+    fn score(&self, free: &Rect, w: u32, h: u32) -> i64 {
+        let leftover_w = i64::from(free.w - w);
+        let leftover_h = i64::from(free.h - h);
+        let leftover_area = i64::from(free.w) * i64::from(free.h) - i64::from(w) * i64::from(h);
+
+        match self.heuristic.choice {
+            GuillotineFreeRectChoice::BestAreaFit => leftover_area,
+            GuillotineFreeRectChoice::BestShortSideFit => min(leftover_w, leftover_h),
+            GuillotineFreeRectChoice::BestLongSideFit => max(leftover_w, leftover_h),
+            GuillotineFreeRectChoice::WorstAreaFit => -leftover_area,
+            GuillotineFreeRectChoice::WorstShortSideFit => -min(leftover_w, leftover_h),
+            GuillotineFreeRectChoice::WorstLongSideFit => -max(leftover_w, leftover_h),
+        }
+    }
+
+    pub(super) fn find_position(
+        &self,
+        w: u32,
+        h: u32,
+        allow_rotation: bool,
+    ) -> Option<(usize, Rect)> {
+        let mut best_score = i64::MAX;
+        let mut best = None;
+
+        for (i, free) in self.free_rects.iter().enumerate() {
+            // a perfect fit can't be beaten
+            if (free.w == w && free.h == h) || (allow_rotation && free.w == h && free.h == w) {
+                let (w, h) = if free.w == w { (w, h) } else { (h, w) };
+                return Some((i, Rect::new(free.x, free.y, w, h)));
+            }
+
+            if free.w >= w && free.h >= h {
+                let score = self.score(free, w, h);
+                if best.is_none() || score < best_score {
+                    best_score = score;
+                    best = Some((i, Rect::new(free.x, free.y, w, h)));
+                }
+            }
+
+            if allow_rotation && free.w >= h && free.h >= w {
+                let score = self.score(free, h, w);
+                if best.is_none() || score < best_score {
+                    best_score = score;
+                    best = Some((i, Rect::new(free.x, free.y, h, w)));
+                }
+            }
+        }
+
+        best
+    }
+
+    // put `rect` returned by `find_position` into the free rectangle `i`
+    pub(super) fn place(&mut self, i: usize, rect: &Rect) {
+        let free = self.free_rects.swap_remove(i);
+        self.split(&free, rect);
+        if self.heuristic.merge {
+            self.merge();
+        }
+    }
+
+    fn split(&mut self, free: &Rect, placed: &Rect) {
+        let leftover_w = free.w - placed.w;
+        let leftover_h = free.h - placed.h;
+
+        let split_horizontal = match self.heuristic.split {
+            GuillotineSplitRule::ShorterLeftoverAxis => leftover_w <= leftover_h,
+            GuillotineSplitRule::LongerLeftoverAxis => leftover_w > leftover_h,
+            GuillotineSplitRule::MinimizeArea => {
+                u64::from(placed.w) * u64::from(leftover_h)
+                    > u64::from(leftover_w) * u64::from(placed.h)
+            }
+            GuillotineSplitRule::MaximizeArea => {
+                u64::from(placed.w) * u64::from(leftover_h)
+                    <= u64::from(leftover_w) * u64::from(placed.h)
+            }
+            GuillotineSplitRule::ShorterAxis => free.w <= free.h,
+            GuillotineSplitRule::LongerAxis => free.w > free.h,
+        };
+
+        //
+        // horizontal split       vertical split
+        // +------+-------+       +------+-------+
+        // |placed| right |       |placed|       |
+        // +------+-------+       +------+ right |
+        // |    bottom    |       |bottom|       |
+        // +--------------+       +------+-------+
+        //
+        let (bottom, right) = if split_horizontal {
+            (
+                Rect::new(free.x, placed.y + placed.h, free.w, leftover_h),
+                Rect::new(placed.x + placed.w, free.y, leftover_w, placed.h),
+            )
+        } else {
+            (
+                Rect::new(free.x, placed.y + placed.h, placed.w, leftover_h),
+                Rect::new(placed.x + placed.w, free.y, leftover_w, free.h),
+            )
+        };
+
+        if bottom.w > 0 && bottom.h > 0 {
+            self.free_rects.push(bottom);
+        }
+        if right.w > 0 && right.h > 0 {
+            self.free_rects.push(right);
+        }
+    }
+
+    // replace `free` by the disjoint parts of it not covered by `used`
+    fn cut(&mut self, free: &Rect, used: &Rect) {
+        let top = max(free.y, used.y);
+        let bottom = min(free.y + free.h, used.y + used.h);
+
+        //
+        // +----------------+
+        // |     above      |
+        // +------+----+----+
+        // | left |used|right
+        // +------+----+----+
+        // |     below      |
+        // +----------------+
+        //
+        let parts = [
+            Rect::new(free.x, free.y, free.w, top - free.y),
+            Rect::new(free.x, bottom, free.w, free.y + free.h - bottom),
+            Rect::new(free.x, top, used.x.saturating_sub(free.x), bottom - top),
+            Rect::new(
+                used.x + used.w,
+                top,
+                (free.x + free.w).saturating_sub(used.x + used.w),
+                bottom - top,
+            ),
+        ];
+        for part in parts {
+            if part.w > 0 && part.h > 0 {
+                self.free_rects.push(part);
+            }
+        }
+    }
+
+    // merge pairs of free rectangles which together form a rectangle
+    fn merge(&mut self) {
+        let mut i = 0;
+        while i < self.free_rects.len() {
+            let mut j = i + 1;
+            while j < self.free_rects.len() {
+                let a = self.free_rects[i];
+                let b = self.free_rects[j];
+
+                let merged = if a.x == b.x && a.w == b.w && a.y + a.h == b.y {
+                    Some(Rect::new(a.x, a.y, a.w, a.h + b.h))
+                } else if a.x == b.x && a.w == b.w && b.y + b.h == a.y {
+                    Some(Rect::new(a.x, b.y, a.w, a.h + b.h))
+                } else if a.y == b.y && a.h == b.h && a.x + a.w == b.x {
+                    Some(Rect::new(a.x, a.y, a.w + b.w, a.h))
+                } else if a.y == b.y && a.h == b.h && b.x + b.w == a.x {
+                    Some(Rect::new(b.x, a.y, a.w + b.w, a.h))
+                } else {
+                    None
+                };
+
+                if let Some(merged) = merged {
+                    self.free_rects[i] = merged;
+                    self.free_rects.remove(j);
+                    // the grown rectangle may now merge with one already visited
+                    j = i + 1;
+                } else {
+                    j += 1;
+                }
+            }
+            i += 1;
+        }
+    }
+}
+
+impl<K> Packer<K> for GuillotinePacker {
+    fn pack(&mut self, key: K, texture_rect: &Rect) -> Option<Frame<K>> {
+        let config = self.config;
+        self.pack_with(key, texture_rect, &config)
+    }
+
+    fn can_pack(&self, texture_rect: &Rect) -> bool {
+        Packer::<K>::can_pack_with(self, texture_rect, &self.config)
+    }
+
+    fn supports_texture_settings(&self) -> bool {
+        true
+    }
+
+    fn pack_with(
+        &mut self,
+        key: K,
+        texture_rect: &Rect,
+        config: &TexturePackerConfig,
+    ) -> Option<Frame<K>> {
+        let mut width = texture_rect.w;
+        let mut height = texture_rect.h;
+
+        width += config.texture_padding + config.texture_extrusion * 2;
+        height += config.texture_padding + config.texture_extrusion * 2;
+
+        if let Some((i, mut rect)) = self.find_position(width, height, config.allow_rotation) {
+            self.place(i, &rect);
+
+            let rotated = width != rect.w;
+
+            rect.w -= config.texture_padding + config.texture_extrusion * 2;
+            rect.h -= config.texture_padding + config.texture_extrusion * 2;
+
+            Some(Frame {
+                key,
+                frame: rect,
+                rotated,
+                trimmed: false,
+                source: Rect {
+                    x: 0,
+                    y: 0,
+                    w: texture_rect.w,
+                    h: texture_rect.h,
+                },
+            })
+        } else {
+            None
+        }
+    }
+
+    fn can_pack_with(&self, texture_rect: &Rect, config: &TexturePackerConfig) -> bool {
+        self.find_position(
+            texture_rect.w + config.texture_padding + config.texture_extrusion * 2,
+            texture_rect.h + config.texture_padding + config.texture_extrusion * 2,
+            config.allow_rotation,
+        )
+        .is_some()
+    }
+
+    fn free(&mut self, frame: &Rect) -> bool {
+        let config = self.config;
+        Packer::<K>::free_with(self, frame, &config)
+    }
+
+    fn free_with(&mut self, frame: &Rect, config: &TexturePackerConfig) -> bool {
+        let freed = reserved_area(config, frame);
+        // an area which is free already, e.g. freed twice, must not be handed out twice
+        if self.free_rects.iter().any(|free| overlaps(free, &freed)) {
+            return false;
+        }
+        self.add_free_rect(freed);
+        true
+    }
+
+    fn reserve(&mut self, frame: &Rect) -> bool {
+        let reserved = reserved_area(&self.config, frame);
+        let mut i = 0;
+        while i < self.free_rects.len() {
+            let free = self.free_rects[i];
+            if overlaps(&free, &reserved) {
+                self.free_rects.swap_remove(i);
+                self.cut(&free, &reserved);
+            } else {
+                i += 1;
+            }
+        }
+        if self.heuristic.merge {
+            self.merge();
+        }
+        true
+    }
+
+    fn boxed_clone(&self) -> Option<Box<dyn Packer<K>>> {
+        Some(Box::new(self.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> TexturePackerConfig {
+        TexturePackerConfig {
+            max_width: 8,
+            max_height: 8,
+            allow_rotation: false,
+            texture_padding: 0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn free_rects_stay_disjoint() {
+        let heuristic = GuillotineHeuristic {
+            choice: GuillotineFreeRectChoice::BestShortSideFit,
+            split: GuillotineSplitRule::MinimizeArea,
+            merge: false,
+        };
+        let mut packer = GuillotinePacker::new(config(), heuristic);
+
+        Packer::<()>::pack(&mut packer, (), &Rect::new(0, 0, 3, 5)).unwrap();
+        Packer::<()>::pack(&mut packer, (), &Rect::new(0, 0, 2, 2)).unwrap();
+
+        let free = &packer.free_rects;
+        let area: u32 = free.iter().map(Rect::area).sum();
+        assert_eq!(area, 64 - 15 - 4);
+        for (i, a) in free.iter().enumerate() {
+            for b in free.iter().skip(i + 1) {
+                assert!(
+                    a.x + a.w <= b.x || b.x + b.w <= a.x || a.y + a.h <= b.y || b.y + b.h <= a.y
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn merge_joins_adjacent_free_rects() {
+        let heuristic = GuillotineHeuristic {
+            merge: true,
+            ..Default::default()
+        };
+        let mut packer = GuillotinePacker::new(config(), heuristic);
+        packer.free_rects = vec![
+            Rect::new(0, 0, 4, 4),
+            Rect::new(0, 4, 8, 4),
+            Rect::new(4, 0, 4, 4),
+        ];
+
+        packer.merge();
+        assert_eq!(packer.free_rects, vec![Rect::new(0, 0, 8, 8)]);
+    }
+
+    #[test]
+    fn reserve_cuts_free_rects() {
+        let mut packer = GuillotinePacker::new(config(), GuillotineHeuristic::default());
+        assert!(Packer::<()>::reserve(&mut packer, &Rect::new(3, 3, 2, 2)));
+
+        let free = &packer.free_rects;
+        assert_eq!(free.len(), 4);
+        assert_eq!(free.iter().map(Rect::area).sum::<u32>(), 64 - 4);
+        assert!(free
+            .iter()
+            .all(|rect| !overlaps(rect, &Rect::new(3, 3, 2, 2))));
+    }
+
+    #[test]
+    fn free_rejects_double_free() {
+        let mut packer = GuillotinePacker::new(config(), GuillotineHeuristic::default());
+        let frame = Packer::<()>::pack(&mut packer, (), &Rect::new(0, 0, 4, 4)).unwrap();
+
+        assert!(Packer::<()>::free(&mut packer, &frame.frame));
+        assert!(!Packer::<()>::free(&mut packer, &frame.frame));
+        let area: u32 = packer.free_rects.iter().map(Rect::area).sum();
+        assert_eq!(area, 64);
+    }
+}
@@ -0,0 +1,396 @@
+use crate::{
+    frame::Frame,
+    packer::{reserved_area, Packer},
+    rect::Rect,
+    texture_packer_config::TexturePackerConfig,
+};
+use std::cmp::{max, min};
+
+/// Rule used by the MaxRects packer to choose the free rectangle a texture is placed into.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum MaxRectsHeuristic {
+    /// Place the texture where the shorter leftover side of the free rectangle is minimal.
+    #[default]
+    BestShortSideFit,
+    /// Place the texture where the longer leftover side of the free rectangle is minimal.
+    BestLongSideFit,
+    /// Place the texture into the smallest free rectangle it fits into.
+    BestAreaFit,
+    /// Place the texture as far to the top and then to the left as possible (Tetris style).
+    BottomLeftRule,
+    /// Place the texture where it touches the atlas edges and other textures the most.
+    ContactPointRule,
+}
+
+/// Packer keeping its free space as a list of maximal, possibly overlapping rectangles.
+#[derive(Clone)]
+pub struct MaxRectsPacker {
+    config: TexturePackerConfig,
+    heuristic: MaxRectsHeuristic,
+    border: Rect,
+
+    // maximal free rectangles, these may overlap each other
+    free_rects: Vec<Rect>,
+    used_rects: Vec<Rect>,
+}
+
+impl MaxRectsPacker {
+    /// Create an empty MaxRects packer covering `max_width` x `max_height` of `config`.
+    pub fn new(config: TexturePackerConfig, heuristic: MaxRectsHeuristic) -> Self {
+        let border = Rect::new(0, 0, config.max_width, config.max_height);
+        let free_rects = if border.w > 0 && border.h > 0 {
+            vec![border]
+        } else {
+            Vec::new()
+        };
+
+        MaxRectsPacker {
+            config,
+            heuristic,
+            border,
+            free_rects,
+            used_rects: Vec::new(),
+        }
+    }
+
+    pub(crate) fn heuristic(&self) -> MaxRectsHeuristic {
+        self.heuristic
+    }
+
+    pub(crate) fn free_rects(&self) -> &[Rect] {
+        &self.free_rects
+    }
+
+    // score of putting a rectangle (w, h) at the top-left of `free`, lower is better
+    fn score(&self, free: &Rect, w: u32, h: u32) -> (u64, u64) {
+        let leftover_w = u64::from(free.w - w);
+        let leftover_h = u64::from(free.h - h);
+        let short_side = min(leftover_w, leftover_h);
+        let long_side = max(leftover_w, leftover_h);
+
+        match self.heuristic {
+            MaxRectsHeuristic::BestShortSideFit => (short_side, long_side),
+            MaxRectsHeuristic::BestLongSideFit => (long_side, short_side),
+            MaxRectsHeuristic::BestAreaFit => {
+                let free_area = u64::from(free.w) * u64::from(free.h);
+                (free_area - u64::from(w) * u64::from(h), short_side)
+            }
+            MaxRectsHeuristic::BottomLeftRule => (u64::from(free.y + h), u64::from(free.x)),
+            MaxRectsHeuristic::ContactPointRule => {
+                let contact = self.contact_score(&Rect::new(free.x, free.y, w, h));
+                (u64::MAX - contact, 0)
+            }
+        }
+    }
+
+    // total length of the edges of `rect` touching the border or any used rectangle
+    fn contact_score(&self, rect: &Rect) -> u64 {
+        let mut score = 0;
+
+        if rect.x == 0 || rect.x + rect.w == self.border.w {
+            score += u64::from(rect.h);
+        }
+        if rect.y == 0 || rect.y + rect.h == self.border.h {
+            score += u64::from(rect.w);
+        }
+
+        for used in &self.used_rects {
+            if used.x == rect.x + rect.w || used.x + used.w == rect.x {
+                score += u64::from(common_interval(used.y, used.h, rect.y, rect.h));
+            }
+            if used.y == rect.y + rect.h || used.y + used.h == rect.y {
+                score += u64::from(common_interval(used.x, used.w, rect.x, rect.w));
+            }
+        }
+
+        score
+    }
+
+    fn find_position(&self, w: u32, h: u32, allow_rotation: bool) -> Option<Rect> {
+        let mut best_score = (u64::MAX, u64::MAX);
+        let mut best = None;
+
+        for free in &self.free_rects {
+            if free.w >= w && free.h >= h {
+                let score = self.score(free, w, h);
+                if best.is_none() || score < best_score {
+                    best_score = score;
+                    best = Some(Rect::new(free.x, free.y, w, h));
+                }
+            }
+
+            if allow_rotation && free.w >= h && free.h >= w {
+                let score = self.score(free, h, w);
+                if best.is_none() || score < best_score {
+                    best_score = score;
+                    best = Some(Rect::new(free.x, free.y, h, w));
+                }
+            }
+        }
+
+        best
+    }
+
+    fn place(&mut self, rect: &Rect) {
+        let mut i = 0;
+        while i < self.free_rects.len() {
+            if let Some(splits) = split_free_rect(&self.free_rects[i], rect) {
+                self.free_rects.swap_remove(i);
+                self.free_rects.extend(splits);
+            } else {
+                i += 1;
+            }
+        }
+
+        self.prune();
+        self.used_rects.push(*rect);
+    }
+
+    // the free rectangles left by the used ones, computed from scratch
+    fn rebuild_free_rects(&mut self) {
+        self.free_rects = if self.border.w > 0 && self.border.h > 0 {
+            vec![self.border]
+        } else {
+            Vec::new()
+        };
+
+        let used_rects = std::mem::take(&mut self.used_rects);
+        for used in &used_rects {
+            self.place(used);
+        }
+    }
+
+    // remove the free rectangles which are contained by another one
+    fn prune(&mut self) {
+        let mut i = 0;
+        while i < self.free_rects.len() {
+            let mut j = i + 1;
+            let mut removed = false;
+            while j < self.free_rects.len() {
+                if self.free_rects[j].contains(&self.free_rects[i]) {
+                    self.free_rects.remove(i);
+                    removed = true;
+                    break;
+                }
+                if self.free_rects[i].contains(&self.free_rects[j]) {
+                    self.free_rects.remove(j);
+                } else {
+                    j += 1;
+                }
+            }
+            if !removed {
+                i += 1;
+            }
+        }
+    }
+}
+
+// length of the overlap of the intervals [a, a + a_len) and [b, b + b_len)
+fn common_interval(a: u32, a_len: u32, b: u32, b_len: u32) -> u32 {
+    let start = max(a, b);
+    let end = min(a + a_len, b + b_len);
+    end.saturating_sub(start)
+}
+
+// return the maximal rectangles of `free` left uncovered by `used`, or `None` if they don't
+// overlap at all
+fn split_free_rect(free: &Rect, used: &Rect) -> Option<Vec<Rect>> {
+    if used.x >= free.x + free.w
+        || used.x + used.w <= free.x
+        || used.y >= free.y + free.h
+        || used.y + used.h <= free.y
+    {
+        return None;
+    }
+
+    let mut result = Vec::with_capacity(4);
+
+    if used.x > free.x {
+        result.push(Rect::new(free.x, free.y, used.x - free.x, free.h));
+    }
+    if used.x + used.w < free.x + free.w {
+        let x = used.x + used.w;
+        result.push(Rect::new(x, free.y, free.x + free.w - x, free.h));
+    }
+    if used.y > free.y {
+        result.push(Rect::new(free.x, free.y, free.w, used.y - free.y));
+    }
+    if used.y + used.h < free.y + free.h {
+        let y = used.y + used.h;
+        result.push(Rect::new(free.x, y, free.w, free.y + free.h - y));
+    }
+
+    Some(result)
+}
+
+impl<K> Packer<K> for MaxRectsPacker {
+    fn pack(&mut self, key: K, texture_rect: &Rect) -> Option<Frame<K>> {
+        let config = self.config;
+        self.pack_with(key, texture_rect, &config)
+    }
+
+    fn can_pack(&self, texture_rect: &Rect) -> bool {
+        Packer::<K>::can_pack_with(self, texture_rect, &self.config)
+    }
+
+    fn supports_texture_settings(&self) -> bool {
+        true
+    }
+
+    fn pack_with(
+        &mut self,
+        key: K,
+        texture_rect: &Rect,
+        config: &TexturePackerConfig,
+    ) -> Option<Frame<K>> {
+        let mut width = texture_rect.w;
+        let mut height = texture_rect.h;
+
+        width += config.texture_padding + config.texture_extrusion * 2;
+        height += config.texture_padding + config.texture_extrusion * 2;
+
+        if let Some(mut rect) = self.find_position(width, height, config.allow_rotation) {
+            self.place(&rect);
+
+            let rotated = width != rect.w;
+
+            rect.w -= config.texture_padding + config.texture_extrusion * 2;
+            rect.h -= config.texture_padding + config.texture_extrusion * 2;
+
+            Some(Frame {
+                key,
+                frame: rect,
+                rotated,
+                trimmed: false,
+                source: Rect {
+                    x: 0,
+                    y: 0,
+                    w: texture_rect.w,
+                    h: texture_rect.h,
+                },
+            })
+        } else {
+            None
+        }
+    }
+
+    fn can_pack_with(&self, texture_rect: &Rect, config: &TexturePackerConfig) -> bool {
+        self.find_position(
+            texture_rect.w + config.texture_padding + config.texture_extrusion * 2,
+            texture_rect.h + config.texture_padding + config.texture_extrusion * 2,
+            config.allow_rotation,
+        )
+        .is_some()
+    }
+
+    fn free(&mut self, frame: &Rect) -> bool {
+        let config = self.config;
+        Packer::<K>::free_with(self, frame, &config)
+    }
+
+    fn free_with(&mut self, frame: &Rect, config: &TexturePackerConfig) -> bool {
+        let reserved = reserved_area(config, frame);
+        match self.used_rects.iter().position(|used| *used == reserved) {
+            Some(i) => {
+                self.used_rects.swap_remove(i);
+                self.rebuild_free_rects();
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn reserve(&mut self, frame: &Rect) -> bool {
+        let reserved = reserved_area(&self.config, frame);
+        self.place(&reserved);
+        true
+    }
+
+    fn boxed_clone(&self) -> Option<Box<dyn Packer<K>>> {
+        Some(Box::new(self.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> TexturePackerConfig {
+        TexturePackerConfig {
+            max_width: 8,
+            max_height: 8,
+            allow_rotation: false,
+            texture_padding: 0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn fills_the_whole_atlas() {
+        let heuristics = [
+            MaxRectsHeuristic::BestShortSideFit,
+            MaxRectsHeuristic::BestLongSideFit,
+            MaxRectsHeuristic::BestAreaFit,
+            MaxRectsHeuristic::BottomLeftRule,
+            MaxRectsHeuristic::ContactPointRule,
+        ];
+
+        for heuristic in heuristics.iter() {
+            let mut packer = MaxRectsPacker::new(config(), *heuristic);
+            let mut frames: Vec<Frame<usize>> = Vec::new();
+            for i in 0..4 {
+                frames.push(packer.pack(i, &Rect::new(0, 0, 4, 4)).unwrap());
+            }
+
+            assert!(!Packer::<usize>::can_pack(&packer, &Rect::new(0, 0, 1, 1)));
+            for (i, a) in frames.iter().enumerate() {
+                assert!(Rect::new(0, 0, 8, 8).contains(&a.frame));
+                for b in frames.iter().skip(i + 1) {
+                    assert!(split_free_rect(&a.frame, &b.frame).is_none());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rotates_to_fit() {
+        let config = TexturePackerConfig {
+            max_width: 4,
+            max_height: 8,
+            allow_rotation: true,
+            texture_padding: 0,
+            ..Default::default()
+        };
+        let mut packer = MaxRectsPacker::new(config, MaxRectsHeuristic::BestAreaFit);
+
+        let frame = packer.pack("wide", &Rect::new(0, 0, 8, 4)).unwrap();
+        assert!(frame.rotated);
+        assert_eq!(frame.frame, Rect::new(0, 0, 4, 8));
+    }
+
+    #[test]
+    fn free_restores_maximal_free_rects() {
+        let mut packer = MaxRectsPacker::new(config(), MaxRectsHeuristic::BottomLeftRule);
+        let a = Packer::<()>::pack(&mut packer, (), &Rect::new(0, 0, 4, 8)).unwrap();
+        Packer::<()>::pack(&mut packer, (), &Rect::new(0, 0, 4, 4)).unwrap();
+
+        assert!(Packer::<()>::free(&mut packer, &a.frame));
+        assert!(!Packer::<()>::free(&mut packer, &a.frame));
+        assert_eq!(
+            packer.free_rects,
+            vec![Rect::new(0, 0, 4, 8), Rect::new(0, 4, 8, 4)]
+        );
+    }
+
+    #[test]
+    fn best_area_fit_handles_huge_rects() {
+        let config = TexturePackerConfig {
+            max_width: 70_000,
+            max_height: 70_000,
+            ..config()
+        };
+        let mut packer = MaxRectsPacker::new(config, MaxRectsHeuristic::BestAreaFit);
+        let frame = packer.pack(0, &Rect::new(0, 0, 66_000, 66_000)).unwrap();
+        assert_eq!(frame.frame, Rect::new(0, 0, 66_000, 66_000));
+    }
+}
@@ -1,10 +1,160 @@
-use crate::{frame::Frame, rect::Rect};
+//! Placement algorithms deciding where textures go in an atlas.
+use crate::{frame::Frame, rect::Rect, texture_packer_config::TexturePackerConfig};
+use std::cmp::min;
+use std::rc::Rc;
 
-pub use self::skyline_packer::SkylinePacker;
+pub use self::{
+    guillotine_packer::{
+        GuillotineFreeRectChoice, GuillotineHeuristic, GuillotinePacker, GuillotineSplitRule,
+    },
+    maxrects_packer::{MaxRectsHeuristic, MaxRectsPacker},
+    shelf_packer::{ShelfChoice, ShelfHeuristic, ShelfPacker},
+    skyline_packer::SkylinePacker,
+};
 
+mod guillotine_packer;
+mod maxrects_packer;
+mod shelf_packer;
 mod skyline_packer;
 
+/// Placement algorithm used by a [TexturePacker](crate::TexturePacker).
+///
+/// A packer only deals with rectangles, the texture packer takes care of trimming, copying pixels
+/// and the border of the atlas. Implementations are expected to uphold the following:
+///
+/// - The packer covers an area of `max_width` x `max_height` pixels of the
+///   [TexturePackerConfig] it was created with. The texture packer
+///   offsets every returned frame by `border_padding` afterwards, so the packer must not account
+///   for it.
+/// - For every texture the packer reserves `texture_padding + texture_extrusion * 2` pixels more
+///   than `texture_rect` in both dimensions. Reserved areas never overlap each other and never
+///   leave the covered area.
+/// - The returned [Frame::frame] starts at the top-left corner of the reserved area and has the
+///   size of `texture_rect`, with width and height swapped if the texture was rotated. The
+///   extruded pixels are drawn around it.
+/// - [Frame::rotated] is `true` if the texture was rotated 90 degrees clockwise. Rotation must
+///   only happen if `allow_rotation` is set.
+/// - [Frame::trimmed] and [Frame::source] are overwritten by the texture packer, packers should
+///   return `false` and a rectangle of the size of `texture_rect` at the origin.
 pub trait Packer<K> {
+    /// Find a place for `texture_rect` and reserve it, returning `None` if there is no room left.
+    /// Only the width and height of `texture_rect` are used.
     fn pack(&mut self, key: K, texture_rect: &Rect) -> Option<Frame<K>>;
+    /// Check if `texture_rect` would be placed by a call to [pack](Packer::pack), without
+    /// reserving anything.
     fn can_pack(&self, texture_rect: &Rect) -> bool;
+    /// Check if the packer honours the settings passed to [pack_with](Packer::pack_with) and
+    /// [can_pack_with](Packer::can_pack_with). The default implementation returns `false`, the
+    /// texture packer then fails with `PackError::TextureOptionsUnsupported` for textures whose
+    /// settings differ from the configuration.
+    fn supports_texture_settings(&self) -> bool {
+        false
+    }
+    /// Find a place for `texture_rect` like [pack](Packer::pack), but with the
+    /// `texture_padding`, `texture_extrusion` and `allow_rotation` of `config`, the settings of a
+    /// single texture, instead of those the packer was created with. The other fields of `config`
+    /// are ignored.
+    ///
+    /// The default implementation ignores `config` and calls [pack](Packer::pack), which is
+    /// right as long as the settings equal those of the packer. Texture packers only pass other
+    /// settings if [supports_texture_settings](Packer::supports_texture_settings) returns `true`.
+    fn pack_with(
+        &mut self,
+        key: K,
+        texture_rect: &Rect,
+        config: &TexturePackerConfig,
+    ) -> Option<Frame<K>> {
+        let _ = config;
+        self.pack(key, texture_rect)
+    }
+    /// Check if `texture_rect` would be placed by a call to [pack_with](Packer::pack_with),
+    /// without reserving anything. The default implementation ignores `config` and calls
+    /// [can_pack](Packer::can_pack).
+    fn can_pack_with(&self, texture_rect: &Rect, config: &TexturePackerConfig) -> bool {
+        let _ = config;
+        self.can_pack(texture_rect)
+    }
+    /// Release the area reserved for `frame`, a [Frame::frame] previously returned by
+    /// [pack](Packer::pack), so that later textures can be placed there.
+    ///
+    /// Returns `false` if the packer can't reuse freed space, which is what the default
+    /// implementation does. The area stays reserved in that case.
+    fn free(&mut self, frame: &Rect) -> bool {
+        let _ = frame;
+        false
+    }
+    /// Like [free](Packer::free), for a frame returned by [pack_with](Packer::pack_with) with
+    /// `config`. The default implementation ignores `config` and calls [free](Packer::free).
+    fn free_with(&mut self, frame: &Rect, config: &TexturePackerConfig) -> bool {
+        let _ = config;
+        self.free(frame)
+    }
+    /// Reserve the area of `frame`, along with the padding and extrusion of a packed texture, so
+    /// that no texture is placed there. `frame` can be anywhere within the covered area.
+    ///
+    /// Returns `false` if the packer can't place textures around fixed areas, which is what the
+    /// default implementation does.
+    fn reserve(&mut self, frame: &Rect) -> bool {
+        let _ = frame;
+        false
+    }
+    /// Copy this packer along with the areas reserved so far, so that placements can be tried
+    /// without committing to them.
+    ///
+    /// Returns `None` if the packer can't be copied, which is what the default implementation
+    /// does.
+    fn boxed_clone(&self) -> Option<Box<dyn Packer<K>>> {
+        None
+    }
 }
+
+/// One of the packing algorithms built into this crate, along with its heuristic.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum PackerAlgorithm {
+    /// See [SkylinePacker].
+    #[default]
+    Skyline,
+    /// See [MaxRectsPacker].
+    MaxRects(MaxRectsHeuristic),
+    /// See [GuillotinePacker].
+    Guillotine(GuillotineHeuristic),
+    /// See [ShelfPacker].
+    Shelf(ShelfHeuristic),
+}
+
+impl PackerAlgorithm {
+    /// Create an empty packer running this algorithm.
+    pub fn new_packer<K>(self, config: TexturePackerConfig) -> Box<dyn Packer<K>> {
+        match self {
+            PackerAlgorithm::Skyline => Box::new(SkylinePacker::new(config)),
+            PackerAlgorithm::MaxRects(heuristic) => {
+                Box::new(MaxRectsPacker::new(config, heuristic))
+            }
+            PackerAlgorithm::Guillotine(heuristic) => {
+                Box::new(GuillotinePacker::new(config, heuristic))
+            }
+            PackerAlgorithm::Shelf(heuristic) => Box::new(ShelfPacker::new(config, heuristic)),
+        }
+    }
+}
+
+// the area reserved for `frame` by a packer, clipped to the covered area
+pub(crate) fn reserved_area(config: &TexturePackerConfig, frame: &Rect) -> Rect {
+    let extra = config.texture_padding + config.texture_extrusion * 2;
+    let right = min(frame.x + frame.w + extra, config.max_width);
+    let bottom = min(frame.y + frame.h + extra, config.max_height);
+    Rect::new(
+        frame.x,
+        frame.y,
+        right.saturating_sub(frame.x),
+        bottom.saturating_sub(frame.y),
+    )
+}
+
+// true if `a` and `b` share at least one pixel
+pub(crate) fn overlaps(a: &Rect, b: &Rect) -> bool {
+    a.x < b.x + b.w && b.x < a.x + a.w && a.y < b.y + b.h && b.y < a.y + a.h
+}
+
+// creates a fresh packer for the given configuration
+pub(crate) type PackerFactory<K> = Rc<dyn Fn(TexturePackerConfig) -> Box<dyn Packer<K>>>;
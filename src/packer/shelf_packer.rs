@@ -0,0 +1,335 @@
+use crate::{
+    frame::Frame,
+    packer::{reserved_area, GuillotineHeuristic, GuillotinePacker, Packer},
+    rect::Rect,
+    texture_packer_config::TexturePackerConfig,
+};
+
+/// Rule used by the shelf packer to choose the shelf a texture is placed on.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum ShelfChoice {
+    /// Only use the last shelf, opening a new one when the texture doesn't fit.
+    NextFit,
+    /// Use the first shelf the texture fits on.
+    FirstFit,
+    /// Use the shelf whose height is closest to the height of the texture.
+    #[default]
+    BestHeightFit,
+}
+
+/// Settings of the shelf packing algorithm.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct ShelfHeuristic {
+    /// Rule to choose the shelf a texture is placed on.
+    pub choice: ShelfChoice,
+    /// True to keep track of the space wasted below textures shorter than their shelf and reuse
+    /// it for later textures, along with the space of freed textures. Default value is `false`.
+    pub waste_map: bool,
+}
+
+#[derive(Clone)]
+struct Shelf {
+    y: u32,
+    h: u32,
+    used_w: u32,
+}
+
+enum Placement {
+    Waste(usize, Rect),
+    Shelf(usize, Rect),
+    NewShelf(Rect),
+}
+
+/// Packer placing textures next to each other on horizontal shelves.
+#[derive(Clone)]
+pub struct ShelfPacker {
+    config: TexturePackerConfig,
+    heuristic: ShelfHeuristic,
+    border: Rect,
+
+    // the shelves are sorted by their `y` position, only the last one may grow in height
+    shelves: Vec<Shelf>,
+    waste_map: Option<GuillotinePacker>,
+}
+
+impl ShelfPacker {
+    /// Create an empty shelf packer covering `max_width` x `max_height` of `config`.
+    pub fn new(config: TexturePackerConfig, heuristic: ShelfHeuristic) -> Self {
+        let waste_map = if heuristic.waste_map {
+            let heuristic = GuillotineHeuristic {
+                merge: true,
+                ..Default::default()
+            };
+            Some(GuillotinePacker::empty(config, heuristic))
+        } else {
+            None
+        };
+
+        ShelfPacker {
+            config,
+            heuristic,
+            border: Rect::new(0, 0, config.max_width, config.max_height),
+            shelves: Vec::new(),
+            waste_map,
+        }
+    }
+
+    // return the size of the rectangle (w, h) as put on the shelf `i`, rotated if that fits better
+    fn fit_on_shelf(&self, i: usize, w: u32, h: u32, allow_rotation: bool) -> Option<(u32, u32)> {
+        let shelf = &self.shelves[i];
+        let max_h = if i + 1 == self.shelves.len() {
+            self.border.h - shelf.y
+        } else {
+            shelf.h
+        };
+
+        let mut best: Option<(u32, u32)> = None;
+        let mut orientations = vec![(w, h)];
+        if allow_rotation {
+            orientations.push((h, w));
+        }
+
+        // prefer the tallest orientation not growing the shelf, then the one growing it least
+        for (w, h) in orientations {
+            if shelf.used_w + w > self.border.w || h > max_h {
+                continue;
+            }
+            let better = match best {
+                None => true,
+                Some((_, best_h)) if best_h > shelf.h => h < best_h,
+                Some((_, best_h)) => h <= shelf.h && h > best_h,
+            };
+            if better {
+                best = Some((w, h));
+            }
+        }
+
+        best
+    }
+
+    fn find_position(&self, w: u32, h: u32, allow_rotation: bool) -> Option<Placement> {
+        if let Some(ref waste_map) = self.waste_map {
+            if let Some((i, rect)) = waste_map.find_position(w, h, allow_rotation) {
+                return Some(Placement::Waste(i, rect));
+            }
+        }
+
+        let first = match self.heuristic.choice {
+            ShelfChoice::NextFit => self.shelves.len().saturating_sub(1),
+            ShelfChoice::FirstFit | ShelfChoice::BestHeightFit => 0,
+        };
+
+        let mut best_score = u32::MAX;
+        let mut best = None;
+        for i in first..self.shelves.len() {
+            if let Some((w, h)) = self.fit_on_shelf(i, w, h, allow_rotation) {
+                let shelf = &self.shelves[i];
+                let rect = Rect::new(shelf.used_w, shelf.y, w, h);
+                if self.heuristic.choice != ShelfChoice::BestHeightFit {
+                    return Some(Placement::Shelf(i, rect));
+                }
+
+                let score = h.abs_diff(shelf.h);
+                if score < best_score {
+                    best_score = score;
+                    best = Some(Placement::Shelf(i, rect));
+                }
+            }
+        }
+
+        if best.is_some() {
+            return best;
+        }
+
+        let y = self.shelves.last().map_or(0, |shelf| shelf.y + shelf.h);
+        // keep new shelves as low as possible
+        let flat = (h > w && h <= self.border.w) || w > self.border.w;
+        let (w, h) = if allow_rotation && flat {
+            (h, w)
+        } else {
+            (w, h)
+        };
+        if w <= self.border.w && y + h <= self.border.h {
+            Some(Placement::NewShelf(Rect::new(0, y, w, h)))
+        } else {
+            None
+        }
+    }
+
+    fn add_waste(&mut self, rect: Rect) {
+        if let Some(ref mut waste_map) = self.waste_map {
+            waste_map.add_free_rect(rect);
+        }
+    }
+
+    fn place(&mut self, placement: &Placement) -> Rect {
+        match *placement {
+            Placement::Waste(i, rect) => {
+                if let Some(ref mut waste_map) = self.waste_map {
+                    waste_map.place(i, &rect);
+                }
+                rect
+            }
+            Placement::Shelf(i, rect) => {
+                let (y, h, used_w) = {
+                    let shelf = &self.shelves[i];
+                    (shelf.y, shelf.h, shelf.used_w)
+                };
+                if rect.h > h {
+                    self.add_waste(Rect::new(0, y + h, used_w, rect.h - h));
+                    self.shelves[i].h = rect.h;
+                } else {
+                    self.add_waste(Rect::new(rect.x, y + rect.h, rect.w, h - rect.h));
+                }
+                self.shelves[i].used_w += rect.w;
+                rect
+            }
+            Placement::NewShelf(rect) => {
+                if self.heuristic.choice == ShelfChoice::NextFit {
+                    // the last shelf is closed for good
+                    let border = self.border;
+                    let closed = self.shelves.last().map(|shelf| {
+                        Rect::new(shelf.used_w, shelf.y, border.w - shelf.used_w, shelf.h)
+                    });
+                    if let Some(closed) = closed {
+                        self.add_waste(closed);
+                    }
+                }
+                self.shelves.push(Shelf {
+                    y: rect.y,
+                    h: rect.h,
+                    used_w: rect.w,
+                });
+                rect
+            }
+        }
+    }
+}
+
+impl<K> Packer<K> for ShelfPacker {
+    fn pack(&mut self, key: K, texture_rect: &Rect) -> Option<Frame<K>> {
+        let config = self.config;
+        self.pack_with(key, texture_rect, &config)
+    }
+
+    fn can_pack(&self, texture_rect: &Rect) -> bool {
+        Packer::<K>::can_pack_with(self, texture_rect, &self.config)
+    }
+
+    fn supports_texture_settings(&self) -> bool {
+        true
+    }
+
+    fn pack_with(
+        &mut self,
+        key: K,
+        texture_rect: &Rect,
+        config: &TexturePackerConfig,
+    ) -> Option<Frame<K>> {
+        let mut width = texture_rect.w;
+        let mut height = texture_rect.h;
+
+        width += config.texture_padding + config.texture_extrusion * 2;
+        height += config.texture_padding + config.texture_extrusion * 2;
+
+        if let Some(placement) = self.find_position(width, height, config.allow_rotation) {
+            let mut rect = self.place(&placement);
+
+            let rotated = width != rect.w;
+
+            rect.w -= config.texture_padding + config.texture_extrusion * 2;
+            rect.h -= config.texture_padding + config.texture_extrusion * 2;
+
+            Some(Frame {
+                key,
+                frame: rect,
+                rotated,
+                trimmed: false,
+                source: Rect {
+                    x: 0,
+                    y: 0,
+                    w: texture_rect.w,
+                    h: texture_rect.h,
+                },
+            })
+        } else {
+            None
+        }
+    }
+
+    fn can_pack_with(&self, texture_rect: &Rect, config: &TexturePackerConfig) -> bool {
+        self.find_position(
+            texture_rect.w + config.texture_padding + config.texture_extrusion * 2,
+            texture_rect.h + config.texture_padding + config.texture_extrusion * 2,
+            config.allow_rotation,
+        )
+        .is_some()
+    }
+
+    // freed space can only be reused through the waste map
+    fn free(&mut self, frame: &Rect) -> bool {
+        let config = self.config;
+        Packer::<K>::free_with(self, frame, &config)
+    }
+
+    fn free_with(&mut self, frame: &Rect, config: &TexturePackerConfig) -> bool {
+        if self.waste_map.is_none() {
+            return false;
+        }
+
+        self.add_waste(reserved_area(config, frame));
+        true
+    }
+
+    fn boxed_clone(&self) -> Option<Box<dyn Packer<K>>> {
+        Some(Box::new(self.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> TexturePackerConfig {
+        TexturePackerConfig {
+            max_width: 8,
+            max_height: 8,
+            allow_rotation: false,
+            texture_padding: 0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn best_height_fit_picks_closest_shelf() {
+        let heuristic = ShelfHeuristic {
+            choice: ShelfChoice::BestHeightFit,
+            waste_map: false,
+        };
+        let mut packer = ShelfPacker::new(config(), heuristic);
+
+        let a = packer.pack("a", &Rect::new(0, 0, 6, 4)).unwrap();
+        let b = packer.pack("b", &Rect::new(0, 0, 6, 2)).unwrap();
+        let c = packer.pack("c", &Rect::new(0, 0, 2, 2)).unwrap();
+
+        assert_eq!(a.frame, Rect::new(0, 0, 6, 4));
+        assert_eq!(b.frame, Rect::new(0, 4, 6, 2));
+        assert_eq!(c.frame, Rect::new(6, 4, 2, 2));
+    }
+
+    #[test]
+    fn waste_map_reuses_space_below_short_textures() {
+        let heuristic = ShelfHeuristic {
+            choice: ShelfChoice::NextFit,
+            waste_map: true,
+        };
+        let mut packer = ShelfPacker::new(config(), heuristic);
+
+        packer.pack("tall", &Rect::new(0, 0, 4, 8)).unwrap();
+        packer.pack("short", &Rect::new(0, 0, 4, 2)).unwrap();
+        let reused = packer.pack("reused", &Rect::new(0, 0, 4, 6)).unwrap();
+
+        assert_eq!(reused.frame, Rect::new(4, 2, 4, 6));
+        assert!(!Packer::<&str>::can_pack(&packer, &Rect::new(0, 0, 1, 1)));
+    }
+}
@@ -1,6 +1,12 @@
-use crate::{frame::Frame, packer::Packer, rect::Rect, texture_packer_config::TexturePackerConfig};
-use std::cmp::max;
+use crate::{
+    frame::Frame,
+    packer::{reserved_area, Packer},
+    rect::Rect,
+    texture_packer_config::TexturePackerConfig,
+};
+use std::cmp::{max, min};
 
+#[derive(Clone)]
 struct Skyline {
     pub x: u32,
     pub y: u32,
@@ -19,6 +25,11 @@ impl Skyline {
     }
 }
 
+/// Packer using the skyline heuristic, keeping track of the top edge of the packed textures.
+///
+/// The space of freed textures is not reused since it may lie below the skyline. Reserved areas
+/// raise the skyline above them, so the space below them is lost as well.
+#[derive(Clone)]
 pub struct SkylinePacker {
     config: TexturePackerConfig,
     border: Rect,
@@ -28,6 +39,7 @@ pub struct SkylinePacker {
 }
 
 impl SkylinePacker {
+    /// Create an empty skyline packer covering `max_width` x `max_height` of `config`.
     pub fn new(config: TexturePackerConfig) -> Self {
         let skylines = vec![Skyline {
             x: 0,
@@ -61,9 +73,9 @@ impl SkylinePacker {
         }
     }
 
-    fn find_skyline(&self, w: u32, h: u32) -> Option<(usize, Rect)> {
-        let mut bottom = std::u32::MAX;
-        let mut width = std::u32::MAX;
+    fn find_skyline(&self, w: u32, h: u32, allow_rotation: bool) -> Option<(usize, Rect)> {
+        let mut bottom = u32::MAX;
+        let mut width = u32::MAX;
         let mut index = None;
         let mut rect = Rect::new(0, 0, 0, 0);
 
@@ -78,7 +90,7 @@ impl SkylinePacker {
                 }
             }
 
-            if self.config.allow_rotation {
+            if allow_rotation {
                 if let Some(r) = self.can_put(i, h, w) {
                     if r.bottom() < bottom || (r.bottom() == bottom && self.skylines[i].w < width) {
                         bottom = r.bottom();
@@ -101,7 +113,8 @@ impl SkylinePacker {
         };
 
         assert!(skyline.right() <= self.border.right());
-        assert!(skyline.y <= self.border.bottom());
+        // a skyline right below the border is full
+        assert!(skyline.y <= self.border.bottom() + 1);
 
         self.skylines.insert(index, skyline);
 
@@ -124,6 +137,44 @@ impl SkylinePacker {
         }
     }
 
+    // lift the skylines over the width of `rect` to its bottom edge
+    fn raise(&mut self, rect: &Rect) {
+        let (left, right) = (rect.x, rect.x + rect.w);
+        let mut skylines = Vec::with_capacity(self.skylines.len() + 2);
+
+        for skyline in self.skylines.drain(..) {
+            let (x, end) = (skyline.x, skyline.x + skyline.w);
+            if end <= left || x >= right {
+                skylines.push(skyline);
+                continue;
+            }
+
+            if x < left {
+                skylines.push(Skyline {
+                    x,
+                    y: skyline.y,
+                    w: left - x,
+                });
+            }
+            let (x_covered, end_covered) = (max(x, left), min(end, right));
+            skylines.push(Skyline {
+                x: x_covered,
+                y: max(skyline.y, rect.y + rect.h),
+                w: end_covered - x_covered,
+            });
+            if end > right {
+                skylines.push(Skyline {
+                    x: right,
+                    y: skyline.y,
+                    w: end - right,
+                });
+            }
+        }
+
+        self.skylines = skylines;
+        self.merge();
+    }
+
     fn merge(&mut self) {
         let mut i = 1;
         while i < self.skylines.len() {
@@ -139,20 +190,38 @@ impl SkylinePacker {
 
 impl<K> Packer<K> for SkylinePacker {
     fn pack(&mut self, key: K, texture_rect: &Rect) -> Option<Frame<K>> {
+        let config = self.config;
+        self.pack_with(key, texture_rect, &config)
+    }
+
+    fn can_pack(&self, texture_rect: &Rect) -> bool {
+        Packer::<K>::can_pack_with(self, texture_rect, &self.config)
+    }
+
+    fn supports_texture_settings(&self) -> bool {
+        true
+    }
+
+    fn pack_with(
+        &mut self,
+        key: K,
+        texture_rect: &Rect,
+        config: &TexturePackerConfig,
+    ) -> Option<Frame<K>> {
         let mut width = texture_rect.w;
         let mut height = texture_rect.h;
 
-        width += self.config.texture_padding + self.config.texture_extrusion * 2;
-        height += self.config.texture_padding + self.config.texture_extrusion * 2;
+        width += config.texture_padding + config.texture_extrusion * 2;
+        height += config.texture_padding + config.texture_extrusion * 2;
 
-        if let Some((i, mut rect)) = self.find_skyline(width, height) {
+        if let Some((i, mut rect)) = self.find_skyline(width, height, config.allow_rotation) {
             self.split(i, &rect);
             self.merge();
 
             let rotated = width != rect.w;
 
-            rect.w -= self.config.texture_padding + self.config.texture_extrusion * 2;
-            rect.h -= self.config.texture_padding + self.config.texture_extrusion * 2;
+            rect.w -= config.texture_padding + config.texture_extrusion * 2;
+            rect.h -= config.texture_padding + config.texture_extrusion * 2;
 
             Some(Frame {
                 key,
@@ -171,19 +240,50 @@ impl<K> Packer<K> for SkylinePacker {
         }
     }
 
-    fn can_pack(&self, texture_rect: &Rect) -> bool {
-        if let Some((_, rect)) = self.find_skyline(
-            texture_rect.w + self.config.texture_padding + self.config.texture_extrusion * 2,
-            texture_rect.h + self.config.texture_padding + self.config.texture_extrusion * 2,
-        ) {
-            let skyline = Skyline {
-                x: rect.left(),
-                y: rect.bottom() + 1,
-                w: rect.w,
-            };
-
-            return skyline.right() <= self.border.right() && skyline.y <= self.border.bottom();
+    fn can_pack_with(&self, texture_rect: &Rect, config: &TexturePackerConfig) -> bool {
+        self.find_skyline(
+            texture_rect.w + config.texture_padding + config.texture_extrusion * 2,
+            texture_rect.h + config.texture_padding + config.texture_extrusion * 2,
+            config.allow_rotation,
+        )
+        .is_some()
+    }
+
+    fn reserve(&mut self, frame: &Rect) -> bool {
+        let reserved = reserved_area(&self.config, frame);
+        if reserved.w > 0 && reserved.h > 0 {
+            self.raise(&reserved);
         }
-        false
+        true
+    }
+
+    fn boxed_clone(&self) -> Option<Box<dyn Packer<K>>> {
+        Some(Box::new(self.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_the_bottom_edge() {
+        let config = TexturePackerConfig {
+            max_width: 8,
+            max_height: 4,
+            allow_rotation: false,
+            texture_padding: 0,
+            ..Default::default()
+        };
+        let mut packer = SkylinePacker::new(config);
+
+        let rect = Rect::new(0, 0, 4, 4);
+        assert!(Packer::<usize>::can_pack(&packer, &rect));
+        let frame = packer.pack(0, &rect).unwrap();
+        assert_eq!(frame.frame, Rect::new(0, 0, 4, 4));
+
+        let frame = packer.pack(1, &rect).unwrap();
+        assert_eq!(frame.frame, Rect::new(4, 0, 4, 4));
+        assert!(!Packer::<usize>::can_pack(&packer, &Rect::new(0, 0, 1, 1)));
     }
 }
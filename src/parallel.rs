@@ -0,0 +1,35 @@
+//! Helpers running work on multiple threads when the `rayon` feature is enabled.
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+// apply `f` to all `items`, keeping their order regardless of the number of threads
+#[cfg(feature = "rayon")]
+pub(crate) fn map<I, R, F>(items: &[I], f: F) -> Vec<R>
+where
+    I: Sync,
+    R: Send,
+    F: Fn(&I) -> R + Sync + Send,
+{
+    items.par_iter().map(f).collect()
+}
+
+// apply `f` to all `items`, keeping their order regardless of the number of threads
+#[cfg(not(feature = "rayon"))]
+pub(crate) fn map<I, R, F>(items: &[I], f: F) -> Vec<R>
+where
+    F: Fn(&I) -> R,
+{
+    items.iter().map(f).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn map_keeps_order() {
+        let items: Vec<u32> = (0..1000).collect();
+        let squares = map(&items, |i| i * i);
+        assert_eq!(squares, items.iter().map(|i| i * i).collect::<Vec<_>>());
+    }
+}
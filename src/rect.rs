@@ -1,7 +1,7 @@
 use crate::texture::Texture;
 
 /// Defines a rectangle in pixels with the origin at the top-left of the texture atlas.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct Rect {
     /// Horizontal position the rectangle begins at.
     pub x: u32,
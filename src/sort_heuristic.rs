@@ -0,0 +1,35 @@
+/// Order in which a batch of textures is packed.
+///
+/// Packing large textures first usually leaves less unusable space in the atlas. All orders are
+/// descending and based on the trimmed texture size, ties keep the order of the input.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum SortHeuristic {
+    /// Keep the order of the input.
+    None,
+    /// Largest area first.
+    #[default]
+    Area,
+    /// Longest side first.
+    MaxSide,
+    /// Largest perimeter first.
+    Perimeter,
+    /// Tallest first.
+    Height,
+    /// Widest first.
+    Width,
+}
+
+impl SortHeuristic {
+    // sort key of a texture of size (w, h), larger keys are packed first
+    pub(crate) fn key(self, w: u32, h: u32) -> (u64, u64) {
+        let (w, h) = (u64::from(w), u64::from(h));
+        match self {
+            SortHeuristic::None => (0, 0),
+            SortHeuristic::Area => (w * h, w.max(h)),
+            SortHeuristic::MaxSide => (w.max(h), w.min(h)),
+            SortHeuristic::Perimeter => (w + h, w.max(h)),
+            SortHeuristic::Height => (h, w),
+            SortHeuristic::Width => (w, h),
+        }
+    }
+}
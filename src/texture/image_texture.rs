@@ -31,6 +31,13 @@ impl<T: Primitive> Pixel for Rgba<T> {
         self[3] == T::DEFAULT_MIN_VALUE
     }
 
+    fn is_transparent_within(&self, threshold: u8) -> bool {
+        match (self[3].to_f64(), T::DEFAULT_MAX_VALUE.to_f64()) {
+            (Some(alpha), Some(max)) => alpha * 255.0 <= f64::from(threshold) * max,
+            _ => self.is_transparent(),
+        }
+    }
+
     fn transparency() -> Option<Rgba<T>> {
         Some(Rgba([T::DEFAULT_MIN_VALUE; 4]))
     }
@@ -43,6 +50,10 @@ impl<T: Primitive> Pixel for Rgba<T> {
             T::DEFAULT_MAX_VALUE,
         ])
     }
+
+    fn with_transparency(&self) -> Option<Rgba<T>> {
+        Some(Rgba([self[0], self[1], self[2], T::DEFAULT_MIN_VALUE]))
+    }
 }
 
 impl<T: Primitive> Pixel for Rgb<T> {
@@ -19,6 +19,10 @@ impl Pixel for RGBA8 {
         self.a == 0
     }
 
+    fn is_transparent_within(&self, threshold: u8) -> bool {
+        self.a <= threshold
+    }
+
     fn transparency() -> Option<Self> {
         Some(RGBA8 {
             r: 0,
@@ -36,6 +40,10 @@ impl Pixel for RGBA8 {
             a: 255,
         }
     }
+
+    fn with_transparency(&self) -> Option<RGBA8> {
+        Some(RGBA8 { a: 0, ..*self })
+    }
 }
 
 /// Texture from RGBA8 pixel data.
@@ -29,9 +29,20 @@ pub trait Texture {
 
     /// Check if a column of the texture is transparent.
     fn is_column_transparent(&self, col: u32) -> bool {
+        self.is_column_transparent_within(col, 0)
+    }
+
+    /// Check if a row of the texture is transparent.
+    fn is_row_transparent(&self, row: u32) -> bool {
+        self.is_row_transparent_within(row, 0)
+    }
+
+    /// Check if the alpha of every pixel in a column of the texture is at most `threshold`, see
+    /// [Pixel::is_transparent_within].
+    fn is_column_transparent_within(&self, col: u32, threshold: u8) -> bool {
         for y in 0..self.height() {
             if let Some(p) = self.get(col, y) {
-                if !p.is_transparent() {
+                if !p.is_transparent_within(threshold) {
                     return false;
                 }
             }
@@ -39,11 +50,12 @@ pub trait Texture {
         true
     }
 
-    /// Check if a row of the texture is transparent.
-    fn is_row_transparent(&self, row: u32) -> bool {
+    /// Check if the alpha of every pixel in a row of the texture is at most `threshold`, see
+    /// [Pixel::is_transparent_within].
+    fn is_row_transparent_within(&self, row: u32, threshold: u8) -> bool {
         for x in 0..self.width() {
             if let Some(p) = self.get(x, row) {
-                if !p.is_transparent() {
+                if !p.is_transparent_within(threshold) {
                     return false;
                 }
             }
@@ -56,10 +68,22 @@ pub trait Texture {
 pub trait Pixel: Sized {
     /// If the pixel is transparent.
     fn is_transparent(&self) -> bool;
+    /// If the alpha of the pixel, scaled to the range of `0` to `255`, is at most `threshold`.
+    /// The default implementation ignores `threshold` and checks
+    /// [is_transparent](Self::is_transparent).
+    fn is_transparent_within(&self, threshold: u8) -> bool {
+        let _ = threshold;
+        self.is_transparent()
+    }
     /// The transparent value for this pixel type.
     fn transparency() -> Option<Self>;
     /// Outline value for this pixel type.
     fn outline() -> Self;
+    /// A fully transparent pixel with the color of this pixel, used for alpha bleeding. `None` if
+    /// the pixel type can't carry a color while being transparent, which is the default.
+    fn with_transparency(&self) -> Option<Self> {
+        None
+    }
 }
 
 impl<P: Pixel> Texture for Box<dyn Texture<Pixel = P> + 'static> {
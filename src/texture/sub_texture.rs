@@ -6,6 +6,9 @@ use std::borrow::Cow;
 pub struct SubTexture<'a, T: 'a + Clone> {
     texture: Cow<'a, T>,
     source: Rect,
+    // size the source is sampled at
+    w: u32,
+    h: u32,
 }
 
 impl<'a, T: Texture + Clone> SubTexture<'a, T> {
@@ -14,6 +17,8 @@ impl<'a, T: Texture + Clone> SubTexture<'a, T> {
         SubTexture {
             texture: Cow::Owned(texture),
             source,
+            w: source.w,
+            h: source.h,
         }
     }
 
@@ -22,31 +27,44 @@ impl<'a, T: Texture + Clone> SubTexture<'a, T> {
         SubTexture {
             texture: Cow::Borrowed(texture),
             source,
+            w: source.w,
+            h: source.h,
         }
     }
+
+    /// Sample the subset at a size of `w` x `h` pixels, using the nearest pixel of the parent
+    /// texture.
+    pub fn resized(self, w: u32, h: u32) -> SubTexture<'a, T> {
+        SubTexture { w, h, ..self }
+    }
+
+    // position in the parent texture of the pixel at (x, y)
+    fn parent_position(&self, x: u32, y: u32) -> (u32, u32) {
+        let x = u64::from(x) * u64::from(self.source.w) / u64::from(self.w.max(1));
+        let y = u64::from(y) * u64::from(self.source.h) / u64::from(self.h.max(1));
+        (self.source.x + x as u32, self.source.y + y as u32)
+    }
 }
 
 impl<'a, T: Texture + Clone> Texture for SubTexture<'a, T> {
     type Pixel = T::Pixel;
 
     fn width(&self) -> u32 {
-        self.source.w
+        self.w
     }
 
     fn height(&self) -> u32 {
-        self.source.h
+        self.h
     }
 
     fn get(&self, x: u32, y: u32) -> Option<T::Pixel> {
-        let x = self.source.x + x;
-        let y = self.source.y + y;
+        let (x, y) = self.parent_position(x, y);
         self.texture.get(x, y)
     }
 
     fn set(&mut self, x: u32, y: u32, val: T::Pixel) {
+        let (x, y) = self.parent_position(x, y);
         if let Cow::Owned(ref mut t) = self.texture {
-            let x = self.source.x + x;
-            let y = self.source.y + y;
             t.set(x, y, val);
         } else {
             panic!("Can't set pixel by borrowed reference");
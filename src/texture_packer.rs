@@ -1,20 +1,51 @@
+#[cfg(feature = "rayon")]
+use crate::parallel;
 use crate::{
-    frame::Frame,
-    packer::{Packer, SkylinePacker},
+    frame::{Frame, FrameMove},
+    packer::{
+        overlaps, GuillotineHeuristic, GuillotinePacker, MaxRectsHeuristic, MaxRectsPacker, Packer,
+        PackerFactory, ShelfHeuristic, ShelfPacker, SkylinePacker,
+    },
     rect::Rect,
+    sort_heuristic::SortHeuristic,
     texture::{Pixel, SubTexture, Texture},
-    texture_packer_config::TexturePackerConfig,
+    texture_packer_config::{ExtrusionMode, TextureOptions, TexturePackerConfig, TrimMode},
 };
-use std::cmp::min;
-use std::collections::HashMap;
+use std::cmp::{max, min, Reverse};
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
 use std::hash::Hash;
+use std::rc::Rc;
 
+/// Result of packing textures, see [PackError].
 pub type PackResult<T> = Result<T, PackError>;
 
+/// Reasons why textures or areas can't be packed.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum PackError {
+    /// Trimming cut away every pixel of the texture.
     TextureEmpty,
+    /// The texture doesn't fit into the space left in the atlas.
     TextureTooLargeToFitIntoAtlas,
+    /// The packer can't keep an area free, as needed by
+    /// [TexturePacker::add_frame], [TexturePacker::reserve] and [TexturePacker::pin_ref], see
+    /// [Packer::reserve].
+    ReservationUnsupported,
+    /// An area given to [TexturePacker::add_frame], [TexturePacker::reserve] or
+    /// [TexturePacker::pin_ref] reaches outside the atlas or into its border.
+    OutOfBounds,
+    /// An area given to [TexturePacker::add_frame], [TexturePacker::reserve] or
+    /// [TexturePacker::pin_ref] overlaps a frame or a reserved area.
+    Overlapping,
+    /// The textures of a group don't fit together even on an empty page, see
+    /// [MultiTexturePacker::pack_group_ref](crate::MultiTexturePacker::pack_group_ref).
+    GroupTooLargeToFitIntoAtlas,
+    /// A [MultiTexturePacker](crate::MultiTexturePacker) would need more pages than its maximum
+    /// allows.
+    TooManyPages,
+    /// The texture is packed with [TextureOptions] changing the layout, which the packer doesn't
+    /// support, see [Packer::supports_texture_settings].
+    TextureOptionsUnsupported,
 }
 
 /// Packs textures into a single texture atlas.
@@ -22,7 +53,23 @@ pub struct TexturePacker<'a, T: 'a + Clone, K: Clone + Eq + Hash> {
     textures: HashMap<K, SubTexture<'a, T>>,
     frames: HashMap<K, Frame<K>>,
     packer: Box<dyn Packer<K>>,
+    new_packer: PackerFactory<K>,
     config: TexturePackerConfig,
+    // largest output size allowed by the configuration, while `config` may hold a smaller size
+    // chosen by `pack_smallest`
+    max_size: (u32, u32),
+
+    // background of the atlas, e.g. holding the frames added by `add_frame`
+    base: Option<SubTexture<'a, T>>,
+    // areas kept free of textures, in atlas coordinates
+    reserved: Vec<Rect>,
+    // keys of the textures which stay where they were pinned
+    pinned: HashSet<K>,
+    // settings of single textures overriding those of `config`
+    options: HashMap<K, TextureOptions>,
+    // insertion order of the textures, breaking ties when they are laid out anew
+    inserted: HashMap<K, u64>,
+    insertions: u64,
 }
 
 impl<'a, Pix: Pixel, T: 'a + Clone + Texture<Pixel = Pix>, K: Clone + Eq + Hash>
@@ -30,11 +77,62 @@ impl<'a, Pix: Pixel, T: 'a + Clone + Texture<Pixel = Pix>, K: Clone + Eq + Hash>
 {
     /// Create a new packer using the skyline packing algorithm.
     pub fn new_skyline(config: TexturePackerConfig) -> Self {
+        Self::with_packer(config, SkylinePacker::new)
+    }
+
+    /// Create a new packer using the MaxRects packing algorithm, choosing free rectangles with
+    /// the given `heuristic`.
+    pub fn new_maxrects(config: TexturePackerConfig, heuristic: MaxRectsHeuristic) -> Self {
+        Self::with_packer(config, move |config| MaxRectsPacker::new(config, heuristic))
+    }
+
+    /// Create a new packer using the guillotine packing algorithm, keeping the free space as
+    /// rectangles produced by axis-aligned cuts.
+    pub fn new_guillotine(config: TexturePackerConfig, heuristic: GuillotineHeuristic) -> Self {
+        Self::with_packer(config, move |config| {
+            GuillotinePacker::new(config, heuristic)
+        })
+    }
+
+    /// Create a new packer using the shelf packing algorithm, placing textures next to each other
+    /// in rows. Best suited for textures of similar height such as font glyphs.
+    pub fn new_shelf(config: TexturePackerConfig, heuristic: ShelfHeuristic) -> Self {
+        Self::with_packer(config, move |config| ShelfPacker::new(config, heuristic))
+    }
+
+    /// Create a new packer using a custom packing algorithm.
+    ///
+    /// `new_packer` is called with `config` to create the [Packer] deciding where textures are
+    /// placed. A factory is taken rather than a packer because a fresh, empty packer is needed
+    /// whenever the atlas is laid out anew, e.g. by [compact](Self::compact), and possibly at
+    /// another size, e.g. by [pack_smallest_own](Self::pack_smallest_own).
+    pub fn with_packer<P, F>(config: TexturePackerConfig, new_packer: F) -> Self
+    where
+        P: 'static + Packer<K>,
+        F: 'static + Fn(TexturePackerConfig) -> P,
+    {
+        Self::new(
+            config,
+            Rc::new(move |config| -> Box<dyn Packer<K>> { Box::new(new_packer(config)) }),
+        )
+    }
+
+    pub(crate) fn new(config: TexturePackerConfig, new_packer: PackerFactory<K>) -> Self {
+        let max_size = config.largest_size();
+        let config = config.constrained();
         TexturePacker {
             textures: HashMap::new(),
             frames: HashMap::new(),
-            packer: Box::new(SkylinePacker::new(config)),
+            packer: new_packer(config),
+            new_packer,
             config,
+            max_size,
+            base: None,
+            reserved: Vec::new(),
+            pinned: HashSet::new(),
+            options: HashMap::new(),
+            inserted: HashMap::new(),
+            insertions: 0,
         }
     }
 }
@@ -44,72 +142,625 @@ impl<'a, Pix: Pixel, T: Clone + Texture<Pixel = Pix>, K: Clone + Eq + Hash>
 {
     /// Check if the texture can be packed into this packer.
     pub fn can_pack(&self, texture: &'a T) -> bool {
-        let rect = texture.into();
-        self.packer.can_pack(&rect)
+        self.can_insert(&texture.into(), &TextureOptions::default())
+    }
+
+    pub(crate) fn can_insert(&self, rect: &Rect, options: &TextureOptions) -> bool {
+        let fits = |packer: &dyn Packer<K>, config: &TexturePackerConfig| {
+            if options.changes_layout(config) {
+                packer.supports_texture_settings()
+                    && packer.can_pack_with(rect, &options.apply(config))
+            } else {
+                packer.can_pack(rect)
+            }
+        };
+        fits(self.packer.as_ref(), &self.config)
+            || match self.grown_packer() {
+                Some((config, packer)) => fits(packer.as_ref(), &config),
+                None => false,
+            }
     }
 
     /// Pack the `texture` into this packer, taking a reference of the texture object.
     pub fn pack_ref(&mut self, key: K, texture: &'a T) -> PackResult<()> {
         let (w, h) = (texture.width(), texture.height());
-        let source = if self.config.trim {
-            if let Some(texture) = trim_texture(texture) {
-                texture
-            } else {
-                return Err(PackError::TextureEmpty);
+        let trimmed = self.trimmed_rect(texture)?;
+        let source = Rect::new(trimmed.x, trimmed.y, w, h);
+        self.insert(key, SubTexture::from_ref(texture, trimmed), source)
+    }
+
+    /// Pack the `texture` into this packer, taking ownership of the texture object.
+    pub fn pack_own(&mut self, key: K, texture: T) -> PackResult<()> {
+        let (w, h) = (texture.width(), texture.height());
+        let trimmed = self.trimmed_rect(&texture)?;
+        let source = Rect::new(trimmed.x, trimmed.y, w, h);
+        self.insert(key, SubTexture::new(texture, trimmed), source)
+    }
+
+    /// Pack the `texture` into this packer with the settings of `options` instead of those of
+    /// the configuration, taking a reference of the texture object. The options are kept when
+    /// the atlas is laid out anew.
+    ///
+    /// Fails with `PackError::TextureOptionsUnsupported` if the options change the padding,
+    /// extrusion or rotation and the [Packer] doesn't support that, see
+    /// [supports_texture_settings](Packer::supports_texture_settings).
+    pub fn pack_ref_with(
+        &mut self,
+        key: K,
+        texture: &'a T,
+        options: TextureOptions,
+    ) -> PackResult<()> {
+        let (w, h) = (texture.width(), texture.height());
+        let trimmed = trimmed_rect(&options.apply(&self.config), texture)?;
+        let source = Rect::new(trimmed.x, trimmed.y, w, h);
+        self.insert_with(key, SubTexture::from_ref(texture, trimmed), source, options)
+    }
+
+    /// Pack the `texture` into this packer with the settings of `options` instead of those of
+    /// the configuration, taking ownership of the texture object.
+    ///
+    /// See [pack_ref_with](Self::pack_ref_with) for details.
+    pub fn pack_own_with(&mut self, key: K, texture: T, options: TextureOptions) -> PackResult<()> {
+        let (w, h) = (texture.width(), texture.height());
+        let trimmed = trimmed_rect(&options.apply(&self.config), &texture)?;
+        let source = Rect::new(trimmed.x, trimmed.y, w, h);
+        self.insert_with(key, SubTexture::new(texture, trimmed), source, options)
+    }
+
+    /// Pack all `textures` into this packer, taking references of the texture objects.
+    ///
+    /// Unlike calling [pack_ref](Self::pack_ref) repeatedly, all textures are collected and
+    /// trimmed first and then packed in the order given by `order`, which usually gives a much
+    /// denser atlas. Packing stops at the first texture that doesn't fit, the textures packed
+    /// before it are kept.
+    pub fn pack_all_ref<I>(&mut self, textures: I, order: SortHeuristic) -> PackResult<()>
+    where
+        I: IntoIterator<Item = (K, &'a T)>,
+    {
+        let batch = trim_all_ref(&self.config, textures)?;
+        self.insert_all(batch, order)
+    }
+
+    /// Pack all `textures` into this packer, taking ownership of the texture objects.
+    ///
+    /// See [pack_all_ref](Self::pack_all_ref) for details.
+    pub fn pack_all_own<I>(&mut self, textures: I, order: SortHeuristic) -> PackResult<()>
+    where
+        I: IntoIterator<Item = (K, T)>,
+    {
+        let batch = trim_all_own(&self.config, textures)?;
+        self.insert_all(batch, order)
+    }
+
+    /// Pack all `textures` into the smallest atlas that holds them, taking references of the
+    /// texture objects.
+    ///
+    /// Textures are sorted by `order` and then packed at various atlas sizes no larger than
+    /// `max_width` and `max_height`, only considering power-of-two or square sizes if
+    /// `power_of_two` or `square` is configured. The chosen size is used as the atlas size, also
+    /// with `force_max_dimensions`, and the resulting atlas size is returned. Textures packed
+    /// before are laid out anew along with `textures`, ahead of them when `order` ranks them
+    /// equally. Later textures which don't fit the chosen size grow the atlas up to the maximum
+    /// size again, keeping the frames in place, unless the packer can't reserve them.
+    ///
+    /// Nothing is packed if the textures don't fit even at the maximum size.
+    pub fn pack_smallest_ref<I>(
+        &mut self,
+        textures: I,
+        order: SortHeuristic,
+    ) -> PackResult<(u32, u32)>
+    where
+        I: IntoIterator<Item = (K, &'a T)>,
+    {
+        let batch = trim_all_ref(&self.config, textures)?;
+        self.insert_smallest(batch, order)
+    }
+
+    /// Pack all `textures` into the smallest atlas that holds them, taking ownership of the
+    /// texture objects.
+    ///
+    /// See [pack_smallest_ref](Self::pack_smallest_ref) for details.
+    pub fn pack_smallest_own<I>(
+        &mut self,
+        textures: I,
+        order: SortHeuristic,
+    ) -> PackResult<(u32, u32)>
+    where
+        I: IntoIterator<Item = (K, T)>,
+    {
+        let batch = trim_all_own(&self.config, textures)?;
+        self.insert_smallest(batch, order)
+    }
+
+    fn trimmed_rect(&self, texture: &T) -> PackResult<Rect> {
+        trimmed_rect(&self.config, texture)
+    }
+
+    // `source` is the trimmed position within the original texture and the original size
+    pub(crate) fn insert(
+        &mut self,
+        key: K,
+        texture: SubTexture<'a, T>,
+        source: Rect,
+    ) -> PackResult<()> {
+        let rect = (&texture).into();
+        let options = self.options.get(&key);
+        let frame = match place(
+            self.packer.as_mut(),
+            &self.config,
+            options,
+            key.clone(),
+            &rect,
+        ) {
+            Err(PackError::TextureTooLargeToFitIntoAtlas) => {
+                let (config, mut packer) = self
+                    .grown_packer()
+                    .ok_or(PackError::TextureTooLargeToFitIntoAtlas)?;
+                let frame = place(packer.as_mut(), &config, options, key.clone(), &rect)?;
+                self.config = config;
+                self.packer = packer;
+                frame
             }
-        } else {
-            Rect::new(0, 0, w, h)
+            frame => frame?,
         };
-        if !self.packer.can_pack(&source) {
-            return Err(PackError::TextureTooLargeToFitIntoAtlas);
+        self.register(frame, texture, source);
+        Ok(())
+    }
+
+    // a packer of the largest allowed size holding the frames placed so far, after
+    // `pack_smallest` chose a smaller size; `None` if the packer has that size already or can't
+    // reserve the frames
+    fn grown_packer(&self) -> Option<(TexturePackerConfig, Box<dyn Packer<K>>)> {
+        let (width, height) = self.max_size;
+        let config = self.config.sized(width, height);
+        if config.max_width == self.config.max_width && config.max_height == self.config.max_height
+        {
+            return None;
         }
 
-        let texture = SubTexture::from_ref(texture, source);
-        let rect = (&texture).into();
-        if let Some(mut frame) = self.packer.pack(key.clone(), &rect) {
-            frame.frame.x += self.config.border_padding;
-            frame.frame.y += self.config.border_padding;
-            frame.trimmed = self.config.trim;
-            frame.source = source;
-            frame.source.w = w;
-            frame.source.h = h;
-            self.frames.insert(key.clone(), frame);
+        let frames = self.frames.values().map(|frame| frame.frame);
+        let occupied: Vec<Rect> = frames.chain(self.reserved.iter().copied()).collect();
+        let packer = self.new_fixed_packer(config, &occupied).ok()?;
+        Some((config, packer))
+    }
+
+    // insert a texture packed with `options`, which are kept for later layouts
+    pub(crate) fn insert_with(
+        &mut self,
+        key: K,
+        texture: SubTexture<'a, T>,
+        source: Rect,
+        options: TextureOptions,
+    ) -> PackResult<()> {
+        if options == TextureOptions::default() {
+            return self.insert(key, texture, source);
+        }
+
+        self.options.insert(key.clone(), options);
+        let result = self.insert(key.clone(), texture, source);
+        if result.is_err() {
+            self.options.remove(&key);
         }
+        result
+    }
 
-        self.textures.insert(key, texture);
+    // insert all textures of `batch` in this order or none of them, giving `batch` back if they
+    // don't fit or the packer can't be copied to try
+    pub(crate) fn insert_group(
+        &mut self,
+        batch: Vec<(K, SubTexture<'a, T>, Rect)>,
+    ) -> Result<(), Vec<(K, SubTexture<'a, T>, Rect)>> {
+        let place_all = |packer: &mut dyn Packer<K>, config: &TexturePackerConfig| {
+            let mut frames = Vec::with_capacity(batch.len());
+            for (key, texture, _) in &batch {
+                let options = self.options.get(key);
+                let frame = place(packer, config, options, key.clone(), &texture.into()).ok()?;
+                frames.push(frame);
+            }
+            Some(frames)
+        };
+
+        let mut placed = None;
+        if let Some(mut packer) = self.packer.boxed_clone() {
+            if let Some(frames) = place_all(packer.as_mut(), &self.config) {
+                placed = Some((self.config, packer, frames));
+            }
+        }
+        if placed.is_none() {
+            if let Some((config, mut packer)) = self.grown_packer() {
+                if let Some(frames) = place_all(packer.as_mut(), &config) {
+                    placed = Some((config, packer, frames));
+                }
+            }
+        }
+        let (config, packer, frames) = match placed {
+            Some(placed) => placed,
+            None => return Err(batch),
+        };
+
+        self.config = config;
+        self.packer = packer;
+        for (frame, (_, texture, source)) in frames.into_iter().zip(batch) {
+            self.register(frame, texture, source);
+        }
         Ok(())
     }
 
-    /// Pack the `texture` into this packer, taking ownership of the texture object.
-    pub fn pack_own(&mut self, key: K, texture: T) -> PackResult<()> {
-        let (w, h) = (texture.width(), texture.height());
-        let source = if self.config.trim {
-            if let Some(texture) = trim_texture(&texture) {
-                texture
+    // store `frame` placed by the packer along with its texture
+    fn register(&mut self, mut frame: Frame<K>, texture: SubTexture<'a, T>, source: Rect) {
+        frame.frame.x += self.config.border_padding;
+        frame.frame.y += self.config.border_padding;
+        frame.trimmed = self.texture_config(&frame.key).trim;
+        frame.source = source;
+        self.track(&frame.key);
+        self.textures.insert(frame.key.clone(), texture);
+        self.frames.insert(frame.key.clone(), frame);
+    }
+
+    // remember when the texture of `key` was inserted, unless it was inserted before
+    fn track(&mut self, key: &K) {
+        if !self.inserted.contains_key(key) {
+            self.inserted.insert(key.clone(), self.insertions);
+            self.insertions += 1;
+        }
+    }
+
+    // position of the texture of `key` in the insertion order, textures not inserted yet last
+    fn insertion(&self, key: &K) -> u64 {
+        self.inserted.get(key).copied().unwrap_or(u64::MAX)
+    }
+
+    fn insert_all(
+        &mut self,
+        mut batch: Vec<(K, SubTexture<'a, T>, Rect)>,
+        order: SortHeuristic,
+    ) -> PackResult<()> {
+        batch.sort_by_key(|(_, texture, _)| Reverse(order.key(texture.width(), texture.height())));
+        for (key, texture, source) in batch {
+            self.insert(key, texture, source)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn insert_smallest(
+        &mut self,
+        mut batch: Vec<(K, SubTexture<'a, T>, Rect)>,
+        order: SortHeuristic,
+    ) -> PackResult<(u32, u32)> {
+        let added = batch.len();
+        let fixed = self.fixed_rects();
+        let mut movable: Vec<K> = self
+            .textures
+            .keys()
+            .filter(|key| !self.pinned.contains(key))
+            .cloned()
+            .collect();
+        movable.sort_by_key(|key| self.insertion(key));
+        for key in movable {
+            if let Some(texture) = self.textures.remove(&key) {
+                let source = self.frames[&key].source;
+                batch.push((key, texture, source));
+            }
+        }
+
+        let mut indices: Vec<usize> = (0..batch.len()).collect();
+        indices.sort_by_key(|&i| {
+            let (key, texture, _) = &batch[i];
+            let size = order.key(texture.width(), texture.height());
+            (Reverse(size), self.insertion(key))
+        });
+        let rects: Vec<(K, Rect)> = indices
+            .iter()
+            .map(|&i| (batch[i].0.clone(), (&batch[i].1).into()))
+            .collect();
+
+        let (width, height) = match self.smallest_size(&rects, &fixed) {
+            Some(size) => size,
+            None => {
+                // put back what was packed before
+                self.textures
+                    .extend(batch.drain(added..).map(|(key, texture, _)| (key, texture)));
+                return Err(PackError::TextureTooLargeToFitIntoAtlas);
+            }
+        };
+
+        self.config = self.config.sized(width, height);
+        self.packer = self.new_fixed_packer(self.config, &fixed)?;
+        for (key, _, _) in &batch[added..] {
+            self.frames.remove(key);
+        }
+
+        let mut batch: Vec<_> = batch.into_iter().map(Some).collect();
+        for i in indices {
+            if let Some((key, texture, source)) = batch[i].take() {
+                self.insert(key, texture, source)?;
+            }
+        }
+        Ok((self.width(), self.height()))
+    }
+
+    // smallest output size allowed by the configuration whose packer holds all `rects` in this
+    // order
+    fn smallest_size(&self, rects: &[(K, Rect)], fixed: &[Rect]) -> Option<(u32, u32)> {
+        let fits = |width: u32, height: u32| {
+            let config = self.config.sized(width, height);
+            match self.new_fixed_packer(config, fixed) {
+                Ok(mut packer) => rects.iter().all(|(key, rect)| {
+                    let options = self.options.get(key);
+                    place(packer.as_mut(), &config, options, key.clone(), rect).is_ok()
+                }),
+                Err(_) => false,
+            }
+        };
+
+        let widths = SizeSteps::new(&self.config, self.max_size.0);
+        let heights = SizeSteps::new(&self.config, self.max_size.1);
+
+        let mut candidates = Vec::new();
+
+        let sides = min(widths.len(), heights.len());
+        if let Some(i) = lowest(sides, |i| fits(widths.get(i), widths.get(i))) {
+            candidates.push((widths.get(i), widths.get(i)));
+        }
+        if self.config.square {
+            return candidates.pop();
+        }
+
+        // find the lowest height for a sample of widths, then the lowest width for that height
+        let samples = min(widths.len(), 32);
+        for j in 0..samples {
+            let w = if samples == widths.len() {
+                widths.get(j)
             } else {
-                return Err(PackError::TextureEmpty);
+                let i = u64::from(j) * u64::from(widths.len() - 1) / u64::from(samples - 1);
+                widths.get(i as u32)
+            };
+            if let Some(i) = lowest(heights.len(), |i| fits(w, heights.get(i))) {
+                let h = heights.get(i);
+                let w = lowest(widths.len(), |i| fits(widths.get(i), h))
+                    .map_or(w, |i| min(w, widths.get(i)));
+                candidates.push((w, h));
             }
+        }
+
+        // prefer the smallest area, then the most square atlas
+        candidates
+            .into_iter()
+            .min_by_key(|&(w, h)| (u64::from(w) * u64::from(h), w.max(h)))
+    }
+
+    /// Use `base` as the background of the atlas, taking a reference of the texture object.
+    ///
+    /// Its pixels are exported wherever there is no packed texture and the atlas is at least as
+    /// large as `base`. This allows adding textures to an atlas built before, whose frames are
+    /// registered with [add_frame](Self::add_frame).
+    pub fn set_base_ref(&mut self, base: &'a T) {
+        let rect = Rect::new(0, 0, base.width(), base.height());
+        self.base = Some(SubTexture::from_ref(base, rect));
+    }
+
+    /// Use `base` as the background of the atlas, taking ownership of the texture object.
+    ///
+    /// See [set_base_ref](Self::set_base_ref) for details.
+    pub fn set_base_own(&mut self, base: T) {
+        let rect = Rect::new(0, 0, base.width(), base.height());
+        self.base = Some(SubTexture::new(base, rect));
+    }
+
+    /// Add the `frame` of a texture which is already part of the atlas, usually in the base set
+    /// by [set_base_own](Self::set_base_own). Textures packed later are placed around it.
+    ///
+    /// Fails if the packer can't place textures around fixed areas, see [Packer::reserve], if
+    /// the frame is not within `max_width` x `max_height` inside the border or if it overlaps
+    /// another frame or reserved area.
+    pub fn add_frame(&mut self, frame: Frame<K>) -> PackResult<()> {
+        self.check_overlap(&frame.frame)?;
+        reserve_area(self.packer.as_mut(), &self.config, &frame.frame)?;
+        self.frames.insert(frame.key.clone(), frame);
+        Ok(())
+    }
+
+    /// Keep `rect` free of packed textures, e.g. for a block of white pixels drawn by the base.
+    /// `texture_padding` and `texture_extrusion` are kept around it as around textures.
+    ///
+    /// Fails like [add_frame](Self::add_frame).
+    pub fn reserve(&mut self, rect: Rect) -> PackResult<()> {
+        self.check_overlap(&rect)?;
+        reserve_area(self.packer.as_mut(), &self.config, &rect)?;
+        self.reserved.push(rect);
+        Ok(())
+    }
+
+    /// Pack the `texture` at position (`x`, `y`) of the atlas, taking a reference of the texture
+    /// object. Textures packed later are placed around it and it stays in place when the atlas
+    /// is laid out anew.
+    ///
+    /// The texture is neither rotated nor moved by trimming, its trimmed pixels stay where they
+    /// would be without trimming. Fails like [add_frame](Self::add_frame).
+    pub fn pin_ref(&mut self, key: K, texture: &'a T, x: u32, y: u32) -> PackResult<()> {
+        let (w, h) = (texture.width(), texture.height());
+        let trimmed = self.trimmed_rect(texture)?;
+        let source = Rect::new(trimmed.x, trimmed.y, w, h);
+        self.pin(key, SubTexture::from_ref(texture, trimmed), source, x, y)
+    }
+
+    /// Pack the `texture` at position (`x`, `y`) of the atlas, taking ownership of the texture
+    /// object.
+    ///
+    /// See [pin_ref](Self::pin_ref) for details.
+    pub fn pin_own(&mut self, key: K, texture: T, x: u32, y: u32) -> PackResult<()> {
+        let (w, h) = (texture.width(), texture.height());
+        let trimmed = self.trimmed_rect(&texture)?;
+        let source = Rect::new(trimmed.x, trimmed.y, w, h);
+        self.pin(key, SubTexture::new(texture, trimmed), source, x, y)
+    }
+
+    fn pin(
+        &mut self,
+        key: K,
+        texture: SubTexture<'a, T>,
+        source: Rect,
+        x: u32,
+        y: u32,
+    ) -> PackResult<()> {
+        let rect = Rect::new(
+            x + source.x,
+            y + source.y,
+            texture.width(),
+            texture.height(),
+        );
+        self.check_overlap(&rect)?;
+        reserve_area(self.packer.as_mut(), &self.config, &rect)?;
+
+        self.frames.insert(
+            key.clone(),
+            Frame {
+                key: key.clone(),
+                frame: rect,
+                rotated: false,
+                trimmed: self.config.trim,
+                source,
+            },
+        );
+        self.track(&key);
+        self.textures.insert(key.clone(), texture);
+        self.pinned.insert(key);
+        Ok(())
+    }
+
+    // fail if `rect` overlaps a frame or a reserved area
+    fn check_overlap(&self, rect: &Rect) -> PackResult<()> {
+        let mut used = self
+            .frames
+            .values()
+            .map(|frame| &frame.frame)
+            .chain(&self.reserved);
+        if used.any(|used| overlaps(used, rect)) {
+            Err(PackError::Overlapping)
         } else {
-            Rect::new(0, 0, w, h)
-        };
-        if !self.packer.can_pack(&source) {
-            return Err(PackError::TextureTooLargeToFitIntoAtlas);
+            Ok(())
+        }
+    }
+
+    /// Fill the extrusion of the texture of `key` as given by `mode` instead of the
+    /// `extrusion_mode` of the configuration. Returns false if no texture of `key` is packed.
+    pub fn set_extrusion_mode(&mut self, key: &K, mode: ExtrusionMode) -> bool {
+        if !self.textures.contains_key(key) {
+            return false;
         }
+        self.options.entry(key.clone()).or_default().extrusion_mode = Some(mode);
+        true
+    }
 
-        let texture = SubTexture::new(texture, source);
-        let rect = (&texture).into();
-        if let Some(mut frame) = self.packer.pack(key.clone(), &rect) {
+    /// Remove the texture of `key` from this packer, returning its frame and whether its area
+    /// was reclaimed.
+    ///
+    /// The area of the texture is handed back to the [Packer], which reuses it for textures
+    /// packed later if it supports that, see [Packer::free]. Otherwise the area stays unused
+    /// until the textures are laid out anew, e.g. by [compact](Self::compact).
+    pub fn remove(&mut self, key: &K) -> Option<(Frame<K>, bool)> {
+        let frame = self.frames.remove(key)?;
+        self.textures.remove(key);
+        self.pinned.remove(key);
+        self.inserted.remove(key);
+        let config = self.texture_config(key);
+        self.options.remove(key);
+
+        let mut rect = frame.frame;
+        rect.x -= self.config.border_padding;
+        rect.y -= self.config.border_padding;
+        let reclaimed = self.packer.free_with(&rect, &config);
+        Some((frame, reclaimed))
+    }
+
+    /// Lay out all textures anew in the order given by `order`, e.g. to get rid of the space
+    /// scattered by [remove](Self::remove), and return the frames which moved.
+    ///
+    /// A renderer can copy the moved frames within the atlas instead of uploading everything
+    /// again. New positions may overlap the previous positions of other frames, so the copies
+    /// should read from a snapshot of the atlas. Nothing changes if the textures don't fit the
+    /// new layout. Textures which `order` ranks equally are laid out in insertion order.
+    pub fn compact(&mut self, order: SortHeuristic) -> PackResult<Vec<FrameMove<K>>> {
+        let mut rects: Vec<(K, Rect)> = self
+            .textures
+            .iter()
+            .filter(|(key, _)| !self.pinned.contains(key))
+            .map(|(key, texture)| (key.clone(), texture.into()))
+            .collect();
+        rects.sort_by_key(|(key, rect)| (Reverse(order.key(rect.w, rect.h)), self.insertion(key)));
+
+        let mut packer = self.new_fixed_packer(self.config, &self.fixed_rects())?;
+        let mut placed = Vec::with_capacity(rects.len());
+        for (key, rect) in rects {
+            let options = self.options.get(&key);
+            let mut frame = place(packer.as_mut(), &self.config, options, key.clone(), &rect)?;
             frame.frame.x += self.config.border_padding;
             frame.frame.y += self.config.border_padding;
-            frame.trimmed = self.config.trim;
-            frame.source = source;
-            frame.source.w = w;
-            frame.source.h = h;
-            self.frames.insert(key.clone(), frame);
+            placed.push((key, frame));
         }
 
-        self.textures.insert(key, texture);
-        Ok(())
+        let mut moves = Vec::new();
+        for (key, placed) in placed {
+            if let Some(frame) = self.frames.get_mut(&key) {
+                if frame.frame != placed.frame || frame.rotated != placed.rotated {
+                    moves.push(FrameMove {
+                        key,
+                        from: frame.frame,
+                        to: placed.frame,
+                        rotated: frame.rotated != placed.rotated,
+                    });
+                    frame.frame = placed.frame;
+                    frame.rotated = placed.rotated;
+                }
+            }
+        }
+        self.packer = packer;
+        Ok(moves)
+    }
+
+    // true if the frame of `key` stays in place when the textures are laid out anew
+    fn is_fixed(&self, key: &K) -> bool {
+        !self.textures.contains_key(key) || self.pinned.contains(key)
+    }
+
+    // the frames added by `add_frame`, the pinned textures and the reserved areas
+    fn fixed_rects(&self) -> Vec<Rect> {
+        let frames = self.frames.values();
+        frames
+            .filter(|frame| self.is_fixed(&frame.key))
+            .map(|frame| frame.frame)
+            .chain(self.reserved.iter().copied())
+            .collect()
+    }
+
+    // a fresh packer for `config` holding the `fixed` areas
+    fn new_fixed_packer(
+        &self,
+        config: TexturePackerConfig,
+        fixed: &[Rect],
+    ) -> PackResult<Box<dyn Packer<K>>> {
+        let mut packer = (self.new_packer)(config);
+        for rect in fixed {
+            reserve_area(packer.as_mut(), &config, rect)?;
+        }
+        Ok(packer)
+    }
+
+    // the configuration with the options of the texture of `key`
+    fn texture_config(&self, key: &K) -> TexturePackerConfig {
+        texture_config(&self.config, &self.options, key)
+    }
+
+    // the parts of this packer making up the pixels of the atlas
+    pub(crate) fn atlas(&self) -> Atlas<'_, 'a, T, K> {
+        Atlas {
+            textures: &self.textures,
+            frames: &self.frames,
+            base: self.base.as_ref(),
+            reserved: &self.reserved,
+            options: &self.options,
+            config: &self.config,
+        }
     }
 
     /// Get the backing mapping from strings to frames.
@@ -125,25 +776,56 @@ impl<'a, Pix: Pixel, T: Clone + Texture<Pixel = Pix>, K: Clone + Eq + Hash>
             None
         }
     }
+}
 
-    /// Get the frame that overlaps with a specified coordinate.
-    fn get_frame_at(&self, x: u32, y: u32) -> Option<&Frame<K>> {
-        let extrusion = self.config.texture_extrusion;
-
-        for (_, frame) in self.frames.iter() {
-            let mut rect = frame.frame;
+#[cfg(feature = "rayon")]
+impl<'a, Pix: Pixel, T: Clone + Texture<Pixel = Pix> + Sync, K: Clone + Eq + Hash>
+    TexturePacker<'a, T, K>
+{
+    /// Same as [pack_all_ref](Self::pack_all_ref), trimming the textures concurrently.
+    pub fn par_pack_all_ref<I>(&mut self, textures: I, order: SortHeuristic) -> PackResult<()>
+    where
+        I: IntoIterator<Item = (K, &'a T)>,
+    {
+        let batch = par_trim_all_ref(&self.config, textures)?;
+        self.insert_all(batch, order)
+    }
 
-            rect.x = rect.x.saturating_sub(extrusion);
-            rect.y = rect.y.saturating_sub(extrusion);
+    /// Same as [pack_all_own](Self::pack_all_own), trimming the textures concurrently.
+    pub fn par_pack_all_own<I>(&mut self, textures: I, order: SortHeuristic) -> PackResult<()>
+    where
+        I: IntoIterator<Item = (K, T)>,
+    {
+        let batch = par_trim_all_own(&self.config, textures)?;
+        self.insert_all(batch, order)
+    }
 
-            rect.w += extrusion * 2;
-            rect.h += extrusion * 2;
+    /// Same as [pack_smallest_ref](Self::pack_smallest_ref), trimming the textures
+    /// concurrently.
+    pub fn par_pack_smallest_ref<I>(
+        &mut self,
+        textures: I,
+        order: SortHeuristic,
+    ) -> PackResult<(u32, u32)>
+    where
+        I: IntoIterator<Item = (K, &'a T)>,
+    {
+        let batch = par_trim_all_ref(&self.config, textures)?;
+        self.insert_smallest(batch, order)
+    }
 
-            if rect.contains_point(x, y) {
-                return Some(frame);
-            }
-        }
-        None
+    /// Same as [pack_smallest_own](Self::pack_smallest_own), trimming the textures
+    /// concurrently.
+    pub fn par_pack_smallest_own<I>(
+        &mut self,
+        textures: I,
+        order: SortHeuristic,
+    ) -> PackResult<(u32, u32)>
+    where
+        I: IntoIterator<Item = (K, T)>,
+    {
+        let batch = par_trim_all_own(&self.config, textures)?;
+        self.insert_smallest(batch, order)
     }
 }
 
@@ -155,10 +837,43 @@ where
     type Pixel = Pix;
 
     fn width(&self) -> u32 {
-        if self.config.force_max_dimensions {
-            return self.config.max_width;
-        }
+        self.atlas().width()
+    }
+
+    fn height(&self) -> u32 {
+        self.atlas().height()
+    }
 
+    fn get(&self, x: u32, y: u32) -> Option<Pix> {
+        self.atlas().get(x, y)
+    }
+
+    fn set(&mut self, _x: u32, _y: u32, _val: Pix) {
+        panic!("Can't set pixel directly");
+    }
+}
+
+// the parts of a texture packer making up the pixels of its atlas, which unlike the packer can be
+// shared between threads if the textures and keys can
+pub(crate) struct Atlas<'p, 'a, T: Clone, K> {
+    textures: &'p HashMap<K, SubTexture<'a, T>>,
+    frames: &'p HashMap<K, Frame<K>>,
+    base: Option<&'p SubTexture<'a, T>>,
+    reserved: &'p [Rect],
+    options: &'p HashMap<K, TextureOptions>,
+    config: &'p TexturePackerConfig,
+}
+
+impl<'p, 'a, Pix: Pixel, T: Clone + Texture<Pixel = Pix>, K: Clone + Eq + Hash>
+    Atlas<'p, 'a, T, K>
+{
+    // the configuration with the options of the texture of `key`
+    fn texture_config(&self, key: &K) -> TexturePackerConfig {
+        texture_config(self.config, self.options, key)
+    }
+
+    // width of the area covered by frames, reserved areas and the base, including the border
+    fn used_width(&self) -> u32 {
         let mut right = None;
 
         for (_, frame) in self.frames.iter() {
@@ -171,18 +886,22 @@ where
             }
         }
 
-        if let Some(right) = right {
+        for rect in self.reserved {
+            right = max(right, Some(rect.right()));
+        }
+
+        let width = if let Some(right) = right {
             right + 1 + self.config.border_padding
         } else {
             0
-        }
+        };
+        self.base
+            .as_ref()
+            .map_or(width, |base| max(width, base.width()))
     }
 
-    fn height(&self) -> u32 {
-        if self.config.force_max_dimensions {
-            return self.config.max_height;
-        }
-
+    // height of the area covered by frames, reserved areas and the base, including the border
+    fn used_height(&self) -> u32 {
         let mut bottom = None;
 
         for (_, frame) in self.frames.iter() {
@@ -195,11 +914,96 @@ where
             }
         }
 
-        if let Some(bottom) = bottom {
+        for rect in self.reserved {
+            bottom = max(bottom, Some(rect.bottom()));
+        }
+
+        let height = if let Some(bottom) = bottom {
             bottom + 1 + self.config.border_padding
         } else {
             0
+        };
+        self.base
+            .as_ref()
+            .map_or(height, |base| max(height, base.height()))
+    }
+
+    /// Get the frame that overlaps with a specified coordinate.
+    fn get_frame_at(&self, x: u32, y: u32) -> Option<&Frame<K>> {
+        for (_, frame) in self.frames.iter() {
+            let extrusion = self.texture_config(&frame.key).texture_extrusion;
+            let mut rect = frame.frame;
+
+            rect.x = rect.x.saturating_sub(extrusion);
+            rect.y = rect.y.saturating_sub(extrusion);
+
+            rect.w += extrusion * 2;
+            rect.h += extrusion * 2;
+
+            if rect.contains_point(x, y) {
+                return Some(frame);
+            }
         }
+        None
+    }
+
+    // the transparent color of the nearest non-transparent pixel of the texture of `frame` to
+    // the pixel at (x, y) relative to the frame, `None` without alpha bleeding
+    fn bleed(&self, frame: &Frame<K>, x: i64, y: i64) -> Option<Pix> {
+        if self.config.alpha_bleed == 0 {
+            return None;
+        }
+        let texture = self.textures.get(&frame.key)?;
+        let (x, y) = if frame.rotated {
+            (y, i64::from(texture.height()) - 1 - x)
+        } else {
+            (x, y)
+        };
+        let (x, y) = nearest_opaque(texture, x, y, self.config.alpha_bleed)?;
+        texture.get(x, y)?.with_transparency()
+    }
+
+    // alpha bleeding of the pixel at (x, y) in the padding to the right of or below a frame, within
+    // the area reserved for the frame so that the padding of each frame is bled into only once
+    fn bleed_into_padding(&self, x: u32, y: u32) -> Option<Pix> {
+        if self.config.alpha_bleed == 0 {
+            return None;
+        }
+        self.frames.values().find_map(|frame| {
+            let config = self.texture_config(&frame.key);
+            let extrusion = i64::from(config.texture_extrusion);
+            let extra = extrusion + i64::from(config.texture_padding);
+            let x = i64::from(x) - i64::from(frame.frame.x);
+            let y = i64::from(y) - i64::from(frame.frame.y);
+            let within = |v: i64, size: u32| -extrusion <= v && v < i64::from(size) + extra;
+            if within(x, frame.frame.w) && within(y, frame.frame.h) {
+                self.bleed(frame, x, y)
+            } else {
+                None
+            }
+        })
+    }
+}
+
+impl<'p, 'a, Pix, T: Clone, K: Clone + Eq + Hash> Texture for Atlas<'p, 'a, T, K>
+where
+    Pix: Pixel,
+    T: Texture<Pixel = Pix>,
+{
+    type Pixel = Pix;
+
+    fn width(&self) -> u32 {
+        let (width, _) = self
+            .config
+            .atlas_size(self.used_width(), self.used_height());
+        width
+    }
+
+    fn height(&self) -> u32 {
+        let (_, height) = self
+            .config
+            .atlas_size(self.used_width(), self.used_height());
+        height
     }
 
     fn get(&self, x: u32, y: u32) -> Option<Pix> {
@@ -209,22 +1013,40 @@ where
             }
 
             if let Some(texture) = self.textures.get(&frame.key) {
-                let x = x.saturating_sub(frame.frame.x);
-                let y = y.saturating_sub(frame.frame.y);
-
-                return if frame.rotated {
-                    let x = min(x, texture.height() - 1);
-                    let y = min(y, texture.width() - 1);
-                    texture.get_rotated(x, y)
+                let (w, h) = if frame.rotated {
+                    (texture.height(), texture.width())
                 } else {
-                    let x = min(x, texture.width() - 1);
-                    let y = min(y, texture.height() - 1);
-                    texture.get(x, y)
+                    (texture.width(), texture.height())
+                };
+                let mode = self.texture_config(&frame.key).extrusion_mode;
+                let x = mode.position(i64::from(x) - i64::from(frame.frame.x), w);
+                let y = mode.position(i64::from(y) - i64::from(frame.frame.y), h);
+                let sample = |x, y| {
+                    if frame.rotated {
+                        texture.get_rotated(x, y)
+                    } else {
+                        texture.get(x, y)
+                    }
+                };
+
+                return match sample(x, y) {
+                    Some(pixel) if pixel.is_transparent() => Some(
+                        self.bleed(frame, i64::from(x), i64::from(y))
+                            .unwrap_or(pixel),
+                    ),
+                    pixel => pixel,
                 };
             }
         }
 
-        None
+        let base = match self.base {
+            Some(base) if x < base.width() && y < base.height() => base.get(x, y),
+            _ => None,
+        };
+        match base {
+            Some(pixel) if !pixel.is_transparent() => Some(pixel),
+            base => self.bleed_into_padding(x, y).or(base),
+        }
     }
 
     fn set(&mut self, _x: u32, _y: u32, _val: Pix) {
@@ -232,10 +1054,321 @@ where
     }
 }
 
-fn trim_texture<T: Texture>(texture: &T) -> Option<Rect> {
+// `config` with the options of the texture of `key`
+fn texture_config<K: Eq + Hash>(
+    config: &TexturePackerConfig,
+    options: &HashMap<K, TextureOptions>,
+    key: &K,
+) -> TexturePackerConfig {
+    match options.get(key) {
+        Some(options) => options.apply(config),
+        None => *config,
+    }
+}
+
+// nearest non-transparent pixel of `texture` to (x, y), as far as `radius` away, searching
+// outwards from (x, y) so that only the pixels up to the nearest one are read; ties resolve to
+// the topmost and then the leftmost pixel
+fn nearest_opaque<T: Texture>(texture: &T, x: i64, y: i64, radius: u32) -> Option<(u32, u32)> {
+    let opaque = |x: i64, y: i64| match (u32::try_from(x), u32::try_from(y)) {
+        (Ok(x), Ok(y)) if x < texture.width() && y < texture.height() => {
+            texture.get(x, y).is_some_and(|p| !p.is_transparent())
+        }
+        _ => false,
+    };
+    // offsets 0, -1, 1, -2, 2, ... up to `radius`
+    let radius = i64::from(radius);
+    let outwards = || (0..=radius * 2).map(|i| if i % 2 == 1 { -(i + 1) / 2 } else { i / 2 });
+    let max_distance = radius * radius;
+
+    // distance, row and column of the nearest pixel so far
+    let mut best: Option<(i64, i64, i64)> = None;
+    for dy in outwards() {
+        for dx in outwards() {
+            let distance = dx * dx + dy * dy;
+            let limit = match best {
+                Some((d, _, _)) => d,
+                None => max_distance,
+            };
+            if distance > limit {
+                break;
+            }
+            let candidate = (distance, y + dy, x + dx);
+            let closer = match best {
+                Some(best) => candidate < best,
+                None => true,
+            };
+            if closer && opaque(x + dx, y + dy) {
+                best = Some(candidate);
+            }
+        }
+    }
+    best.map(|(_, y, x)| (x as u32, y as u32))
+}
+
+// trim all `textures` and pair each with the trimmed position within the original texture and
+// the original size
+pub(crate) fn trim_all_ref<'a, T, K, I>(
+    config: &TexturePackerConfig,
+    textures: I,
+) -> PackResult<Vec<(K, SubTexture<'a, T>, Rect)>>
+where
+    T: 'a + Clone + Texture,
+    I: IntoIterator<Item = (K, &'a T)>,
+{
+    let (keys, textures): (Vec<K>, Vec<&'a T>) = textures.into_iter().unzip();
+    let trimmed = textures
+        .iter()
+        .map(|texture| trimmed_rect(config, *texture))
+        .collect();
+    batch_ref(config, keys, textures, trimmed)
+}
+
+// same as `trim_all_ref`, trimming the textures concurrently
+#[cfg(feature = "rayon")]
+pub(crate) fn par_trim_all_ref<'a, T, K, I>(
+    config: &TexturePackerConfig,
+    textures: I,
+) -> PackResult<Vec<(K, SubTexture<'a, T>, Rect)>>
+where
+    T: 'a + Clone + Texture + Sync,
+    I: IntoIterator<Item = (K, &'a T)>,
+{
+    let (keys, textures): (Vec<K>, Vec<&'a T>) = textures.into_iter().unzip();
+    let trimmed = parallel::map(&textures, |texture| trimmed_rect(config, *texture));
+    batch_ref(config, keys, textures, trimmed)
+}
+
+// pair `textures` with their `trimmed` rectangles, see `trim_all_ref`
+fn batch_ref<'a, T: 'a + Clone + Texture, K>(
+    config: &TexturePackerConfig,
+    keys: Vec<K>,
+    textures: Vec<&'a T>,
+    trimmed: Vec<PackResult<Rect>>,
+) -> PackResult<Vec<(K, SubTexture<'a, T>, Rect)>> {
+    let sizes: Vec<_> = textures.iter().map(|t| (t.width(), t.height())).collect();
+    let trimmed = common_trim(config, trimmed, &sizes);
+
+    let mut batch = Vec::with_capacity(keys.len());
+    for ((key, texture), trimmed) in keys.into_iter().zip(textures).zip(trimmed) {
+        let trimmed = trimmed?;
+        let source = Rect::new(trimmed.x, trimmed.y, texture.width(), texture.height());
+        batch.push((key, SubTexture::from_ref(texture, trimmed), source));
+    }
+    Ok(batch)
+}
+
+// same as `trim_all_ref` taking ownership of the texture objects
+pub(crate) fn trim_all_own<'a, T, K, I>(
+    config: &TexturePackerConfig,
+    textures: I,
+) -> PackResult<Vec<(K, SubTexture<'a, T>, Rect)>>
+where
+    T: 'a + Clone + Texture,
+    I: IntoIterator<Item = (K, T)>,
+{
+    let (keys, textures): (Vec<K>, Vec<T>) = textures.into_iter().unzip();
+    let trimmed = textures
+        .iter()
+        .map(|texture| trimmed_rect(config, texture))
+        .collect();
+    batch_own(config, keys, textures, trimmed)
+}
+
+// same as `trim_all_own`, trimming the textures concurrently
+#[cfg(feature = "rayon")]
+pub(crate) fn par_trim_all_own<'a, T, K, I>(
+    config: &TexturePackerConfig,
+    textures: I,
+) -> PackResult<Vec<(K, SubTexture<'a, T>, Rect)>>
+where
+    T: 'a + Clone + Texture + Sync,
+    I: IntoIterator<Item = (K, T)>,
+{
+    let (keys, textures): (Vec<K>, Vec<T>) = textures.into_iter().unzip();
+    let trimmed = parallel::map(&textures, |texture| trimmed_rect(config, texture));
+    batch_own(config, keys, textures, trimmed)
+}
+
+// same as `batch_ref` taking ownership of the texture objects
+fn batch_own<'a, T: 'a + Clone + Texture, K>(
+    config: &TexturePackerConfig,
+    keys: Vec<K>,
+    textures: Vec<T>,
+    trimmed: Vec<PackResult<Rect>>,
+) -> PackResult<Vec<(K, SubTexture<'a, T>, Rect)>> {
+    let sizes: Vec<_> = textures.iter().map(|t| (t.width(), t.height())).collect();
+    let trimmed = common_trim(config, trimmed, &sizes);
+
+    let mut batch = Vec::with_capacity(keys.len());
+    for ((key, texture), trimmed) in keys.into_iter().zip(textures).zip(trimmed) {
+        let trimmed = trimmed?;
+        let source = Rect::new(trimmed.x, trimmed.y, texture.width(), texture.height());
+        batch.push((key, SubTexture::new(texture, trimmed), source));
+    }
+    Ok(batch)
+}
+
+// place `rect` with `packer` covering the area of `config`, with the settings of `options` if
+// they change the layout
+fn place<K>(
+    packer: &mut dyn Packer<K>,
+    config: &TexturePackerConfig,
+    options: Option<&TextureOptions>,
+    key: K,
+    rect: &Rect,
+) -> PackResult<Frame<K>> {
+    let frame = match options {
+        Some(options) if options.changes_layout(config) => {
+            if !packer.supports_texture_settings() {
+                return Err(PackError::TextureOptionsUnsupported);
+            }
+            packer.pack_with(key, rect, &options.apply(config))
+        }
+        _ => packer.pack(key, rect),
+    };
+    frame.ok_or(PackError::TextureTooLargeToFitIntoAtlas)
+}
+
+// with `TrimMode::Common`, widen the `trimmed` rects of textures of the given `sizes` to the
+// bounding box of all of them, also for the textures without any pixels
+fn common_trim(
+    config: &TexturePackerConfig,
+    trimmed: Vec<PackResult<Rect>>,
+    sizes: &[(u32, u32)],
+) -> Vec<PackResult<Rect>> {
+    if !config.trim || config.trim_mode != TrimMode::Common {
+        return trimmed;
+    }
+
+    let bounds = trimmed
+        .iter()
+        .flatten()
+        .fold(None, |bounds: Option<Rect>, rect| {
+            Some(match bounds {
+                Some(b) => Rect::new_with_points(
+                    min(b.x, rect.x),
+                    min(b.y, rect.y),
+                    max(b.right(), rect.right()),
+                    max(b.bottom(), rect.bottom()),
+                ),
+                None => *rect,
+            })
+        });
+    let bounds = match bounds {
+        Some(bounds) => bounds,
+        None => return trimmed,
+    };
+
+    trimmed
+        .into_iter()
+        .zip(sizes)
+        .map(|(rect, &(w, h))| match rect {
+            Ok(_) | Err(PackError::TextureEmpty) if bounds.x < w && bounds.y < h => Ok(Rect::new(
+                bounds.x,
+                bounds.y,
+                min(bounds.w, w - bounds.x),
+                min(bounds.h, h - bounds.y),
+            )),
+            rect => rect,
+        })
+        .collect()
+}
+
+// reserve `rect`, given in atlas coordinates, in `packer` covering the area of `config`
+fn reserve_area<K>(
+    packer: &mut dyn Packer<K>,
+    config: &TexturePackerConfig,
+    rect: &Rect,
+) -> PackResult<()> {
+    let border = config.border_padding;
+    if rect.x < border
+        || rect.y < border
+        || rect.x - border + rect.w > config.max_width
+        || rect.y - border + rect.h > config.max_height
+    {
+        return Err(PackError::OutOfBounds);
+    }
+
+    let rect = Rect::new(rect.x - border, rect.y - border, rect.w, rect.h);
+    if packer.reserve(&rect) {
+        Ok(())
+    } else {
+        Err(PackError::ReservationUnsupported)
+    }
+}
+
+// the part of `texture` which actually gets packed
+pub(crate) fn trimmed_rect<T: Texture>(
+    config: &TexturePackerConfig,
+    texture: &T,
+) -> PackResult<Rect> {
+    if config.trim {
+        let trimmed =
+            trim_texture(texture, config.trim_alpha_threshold).ok_or(PackError::TextureEmpty)?;
+        let (w, h) = (texture.width(), texture.height());
+        Ok(config.trim_mode.apply(trimmed, w, h))
+    } else {
+        Ok(Rect::new(0, 0, texture.width(), texture.height()))
+    }
+}
+
+// the atlas dimensions allowed by the configuration, up to `max`
+struct SizeSteps {
+    power_of_two: bool,
+    multiple: u32,
+    max: u32,
+}
+
+impl SizeSteps {
+    fn new(config: &TexturePackerConfig, max: u32) -> Self {
+        SizeSteps {
+            power_of_two: config.power_of_two,
+            multiple: config.size_multiple.max(1),
+            max,
+        }
+    }
+
+    fn len(&self) -> u32 {
+        if self.power_of_two {
+            32 - self.max.leading_zeros()
+        } else {
+            self.max / self.multiple
+        }
+    }
+
+    fn get(&self, i: u32) -> u32 {
+        if self.power_of_two {
+            1 << i
+        } else {
+            (i + 1) * self.multiple
+        }
+    }
+}
+
+// binary search the lowest `i` in `0..len` for which `pred` holds, assuming it holds for every
+// `i` above as well
+fn lowest<F: Fn(u32) -> bool>(len: u32, pred: F) -> Option<u32> {
+    if len == 0 || !pred(len - 1) {
+        return None;
+    }
+
+    let (mut lo, mut hi) = (0, len - 1);
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if pred(mid) {
+            hi = mid;
+        } else {
+            lo = mid + 1;
+        }
+    }
+    Some(hi)
+}
+
+fn trim_texture<T: Texture>(texture: &T, threshold: u8) -> Option<Rect> {
     let mut x1 = 0;
     for x in 0..texture.width() {
-        if texture.is_column_transparent(x) {
+        if texture.is_column_transparent_within(x, threshold) {
             x1 = x + 1;
         } else {
             break;
@@ -249,7 +1382,7 @@ fn trim_texture<T: Texture>(texture: &T) -> Option<Rect> {
     let mut x2 = texture.width() - 1;
     for x in 0..texture.width() {
         let x = texture.width() - x - 1;
-        if texture.is_column_transparent(x) {
+        if texture.is_column_transparent_within(x, threshold) {
             x2 = x - 1;
         } else {
             break;
@@ -258,7 +1391,7 @@ fn trim_texture<T: Texture>(texture: &T) -> Option<Rect> {
 
     let mut y1 = 0;
     for y in 0..texture.height() {
-        if texture.is_row_transparent(y) {
+        if texture.is_row_transparent_within(y, threshold) {
             y1 = y + 1;
         } else {
             break;
@@ -268,7 +1401,7 @@ fn trim_texture<T: Texture>(texture: &T) -> Option<Rect> {
     let mut y2 = texture.height() - 1;
     for y in 0..texture.height() {
         let y = texture.height() - y - 1;
-        if texture.is_row_transparent(y) {
+        if texture.is_row_transparent_within(y, threshold) {
             y2 = y - 1;
         } else {
             break;
@@ -290,6 +1423,719 @@ mod tests {
             _packer: TexturePacker<'a, MemoryRGBA8Texture, String>,
         }
 
-        MyPacker { _packer: packer };
+        let _ = MyPacker { _packer: packer };
+    }
+
+    #[test]
+    fn custom_packer() {
+        // stacks every texture below the previous one
+        struct ColumnPacker {
+            y: u32,
+        }
+
+        impl<K> Packer<K> for ColumnPacker {
+            fn pack(&mut self, key: K, texture_rect: &Rect) -> Option<Frame<K>> {
+                let frame = Rect::new(0, self.y, texture_rect.w, texture_rect.h);
+                self.y += texture_rect.h;
+                Some(Frame {
+                    key,
+                    frame,
+                    rotated: false,
+                    trimmed: false,
+                    source: Rect::new(0, 0, texture_rect.w, texture_rect.h),
+                })
+            }
+
+            fn can_pack(&self, _texture_rect: &Rect) -> bool {
+                true
+            }
+        }
+
+        let config = TexturePackerConfig {
+            trim: false,
+            ..Default::default()
+        };
+        let mut packer = TexturePacker::with_packer(config, |_| ColumnPacker { y: 0 });
+        let texture = MemoryRGBA8Texture::from_memory(&[255; 4 * 6], 2, 3);
+        packer.pack_ref("a", &texture).unwrap();
+        packer.pack_ref("b", &texture).unwrap();
+
+        assert_eq!(packer.get_frame(&"b").unwrap().frame, Rect::new(0, 3, 2, 3));
+        assert_eq!((packer.width(), packer.height()), (2, 6));
+    }
+
+    #[test]
+    fn pack_all_sorts_the_input() {
+        let config = TexturePackerConfig {
+            max_width: 4,
+            max_height: 4,
+            texture_padding: 0,
+            ..Default::default()
+        };
+        let small = MemoryRGBA8Texture::from_memory(&[255; 4], 1, 1);
+        let big = MemoryRGBA8Texture::from_memory(&[255; 4 * 9], 3, 3);
+        let textures = vec![("small", &small), ("big", &big)];
+
+        let mut packer = TexturePacker::new_skyline(config);
+        packer
+            .pack_all_ref(textures.clone(), SortHeuristic::None)
+            .unwrap();
+        assert_eq!(
+            packer.get_frame(&"small").unwrap().frame,
+            Rect::new(0, 0, 1, 1)
+        );
+
+        let mut packer = TexturePacker::new_skyline(config);
+        packer.pack_all_ref(textures, SortHeuristic::Area).unwrap();
+        assert_eq!(
+            packer.get_frame(&"big").unwrap().frame,
+            Rect::new(0, 0, 3, 3)
+        );
+    }
+
+    #[test]
+    fn pack_smallest_finds_the_smallest_atlas() {
+        let config = TexturePackerConfig {
+            max_width: 64,
+            max_height: 64,
+            texture_padding: 0,
+            ..Default::default()
+        };
+        let texture = MemoryRGBA8Texture::from_memory(&[255; 4 * 16], 4, 4);
+        let mut packer = TexturePacker::new_maxrects(config, MaxRectsHeuristic::default());
+        packer.pack_ref(0, &texture).unwrap();
+
+        let textures = (1..4).map(|i| (i, &texture));
+        let size = packer.pack_smallest_ref(textures, SortHeuristic::Area);
+        assert_eq!(size, Ok((8, 8)));
+        assert_eq!(packer.get_frames().len(), 4);
+
+        let huge = MemoryRGBA8Texture::from_memory(&[255; 4 * 65], 65, 1);
+        let size = packer.pack_smallest_ref(vec![(4, &huge)], SortHeuristic::Area);
+        assert_eq!(size, Err(PackError::TextureTooLargeToFitIntoAtlas));
+        assert_eq!(packer.get_frames().len(), 4);
+    }
+
+    #[test]
+    fn pack_smallest_breaks_ties_by_insertion_order() {
+        let config = TexturePackerConfig {
+            max_width: 16,
+            max_height: 4,
+            allow_rotation: false,
+            texture_padding: 0,
+            trim: false,
+            ..Default::default()
+        };
+        let texture = MemoryRGBA8Texture::from_memory(&[255; 4 * 16], 4, 4);
+        let mut packer = TexturePacker::new_maxrects(config, MaxRectsHeuristic::default());
+        for key in ["a", "b", "c"] {
+            packer.pack_ref(key, &texture).unwrap();
+        }
+
+        let size = packer.pack_smallest_ref(vec![("d", &texture)], SortHeuristic::Area);
+        assert_eq!(size, Ok((16, 4)));
+        for (key, x) in [("a", 0), ("b", 4), ("c", 8), ("d", 12)] {
+            assert_eq!(packer.get_frame(&key).unwrap().frame, Rect::new(x, 0, 4, 4));
+        }
+    }
+
+    #[test]
+    fn pack_smallest_keeps_the_maximum_size() {
+        let config = TexturePackerConfig {
+            max_width: 64,
+            max_height: 64,
+            texture_padding: 0,
+            ..Default::default()
+        };
+        let small = MemoryRGBA8Texture::from_memory(&[255; 4 * 16], 4, 4);
+        let large = MemoryRGBA8Texture::from_memory(&[255; 4 * 400], 20, 20);
+        let mut packer = TexturePacker::new_skyline(config);
+        let size = packer.pack_smallest_ref(vec![("a", &small)], SortHeuristic::Area);
+        assert_eq!(size, Ok((4, 4)));
+
+        assert!(packer.can_pack(&large));
+        packer.pack_ref("b", &large).unwrap();
+        assert_eq!(packer.get_frame(&"a").unwrap().frame, Rect::new(0, 0, 4, 4));
+
+        let size = packer.pack_smallest_ref(vec![("c", &large)], SortHeuristic::Area);
+        assert_eq!(size, Ok((20, 44)));
+        assert_eq!(packer.get_frames().len(), 3);
+    }
+
+    #[test]
+    fn output_dimensions_are_rounded() {
+        let texture = MemoryRGBA8Texture::from_memory(&[255; 4 * 5 * 3], 5, 3);
+        let dimensions = |config: TexturePackerConfig| {
+            let mut packer = TexturePacker::new_skyline(TexturePackerConfig {
+                texture_padding: 0,
+                allow_rotation: false,
+                ..config
+            });
+            packer.pack_ref((), &texture).unwrap();
+            (packer.width(), packer.height())
+        };
+
+        let config = TexturePackerConfig::default();
+        assert_eq!(dimensions(config), (5, 3));
+        let power_of_two = TexturePackerConfig {
+            power_of_two: true,
+            ..config
+        };
+        assert_eq!(dimensions(power_of_two), (8, 4));
+        let size_multiple = TexturePackerConfig {
+            size_multiple: 4,
+            ..config
+        };
+        assert_eq!(dimensions(size_multiple), (8, 4));
+        let square = TexturePackerConfig {
+            square: true,
+            size_multiple: 3,
+            ..config
+        };
+        assert_eq!(dimensions(square), (6, 6));
+    }
+
+    #[test]
+    fn rounded_dimensions_stay_within_max() {
+        let config = TexturePackerConfig {
+            max_width: 600,
+            max_height: 600,
+            power_of_two: true,
+            texture_padding: 0,
+            trim: false,
+            ..Default::default()
+        };
+        let mut packer = TexturePacker::new_skyline(config);
+        let wide = MemoryRGBA8Texture::from_memory(&[255; 4 * 520 * 10], 520, 10);
+        let result = packer.pack_ref("wide", &wide);
+        assert_eq!(result, Err(PackError::TextureTooLargeToFitIntoAtlas));
+
+        let texture = MemoryRGBA8Texture::from_memory(&[255; 4 * 500 * 10], 500, 10);
+        packer.pack_ref("texture", &texture).unwrap();
+        assert_eq!((packer.width(), packer.height()), (512, 16));
+    }
+
+    #[test]
+    fn rounded_dimensions_include_border() {
+        let texture = |size: u32| {
+            MemoryRGBA8Texture::from_memory(&vec![255; (4 * size * 10) as usize], size, 10)
+        };
+        let dimensions = |config: TexturePackerConfig, size: u32| {
+            let mut packer = TexturePacker::new_skyline(TexturePackerConfig {
+                texture_padding: 0,
+                allow_rotation: false,
+                trim: false,
+                ..config
+            });
+            let texture = texture(size);
+            packer.pack_ref((), &texture)?;
+            Ok((packer.width(), packer.height()))
+        };
+
+        let power_of_two = TexturePackerConfig {
+            max_width: 600,
+            max_height: 600,
+            power_of_two: true,
+            border_padding: 2,
+            ..Default::default()
+        };
+        let too_large = Err(PackError::TextureTooLargeToFitIntoAtlas);
+        assert_eq!(dimensions(power_of_two, 510), too_large);
+        assert_eq!(dimensions(power_of_two, 508), Ok((512, 16)));
+
+        let size_multiple = TexturePackerConfig {
+            max_width: 1024,
+            max_height: 1024,
+            size_multiple: 4,
+            border_padding: 1,
+            ..Default::default()
+        };
+        assert_eq!(dimensions(size_multiple, 1024), too_large);
+        assert_eq!(dimensions(size_multiple, 1022), Ok((1024, 12)));
+    }
+
+    #[test]
+    fn remove_frees_space() {
+        let config = TexturePackerConfig {
+            max_width: 4,
+            max_height: 4,
+            border_padding: 1,
+            texture_padding: 0,
+            trim: false,
+            ..Default::default()
+        };
+        let mut packer = TexturePacker::new_maxrects(config, MaxRectsHeuristic::default());
+        let texture = MemoryRGBA8Texture::from_memory(&[255; 4 * 16], 4, 4);
+        packer.pack_ref("a", &texture).unwrap();
+        assert!(!packer.can_pack(&texture));
+
+        let (frame, reclaimed) = packer.remove(&"a").unwrap();
+        assert_eq!(frame.frame, Rect::new(1, 1, 4, 4));
+        assert!(reclaimed);
+        assert!(packer.get_frame(&"a").is_none());
+        assert!(packer.remove(&"a").is_none());
+
+        packer.pack_ref("b", &texture).unwrap();
+        assert_eq!(packer.get_frame(&"b").unwrap().frame, Rect::new(1, 1, 4, 4));
+    }
+
+    #[test]
+    fn remove_reports_unreclaimed_space() {
+        let config = TexturePackerConfig {
+            max_width: 4,
+            max_height: 4,
+            texture_padding: 0,
+            trim: false,
+            ..Default::default()
+        };
+        let mut packer = TexturePacker::new_skyline(config);
+        let texture = MemoryRGBA8Texture::from_memory(&[255; 4 * 16], 4, 4);
+        packer.pack_ref("a", &texture).unwrap();
+
+        let (frame, reclaimed) = packer.remove(&"a").unwrap();
+        assert_eq!(frame.frame, Rect::new(0, 0, 4, 4));
+        assert!(!reclaimed);
+        assert!(!packer.can_pack(&texture));
+    }
+
+    #[test]
+    fn remove_frees_space_of_texture_options() {
+        let config = TexturePackerConfig {
+            max_width: 8,
+            max_height: 8,
+            texture_padding: 4,
+            trim: false,
+            ..Default::default()
+        };
+        let mut packer = TexturePacker::new_maxrects(config, MaxRectsHeuristic::default());
+        let small = MemoryRGBA8Texture::from_memory(&[255; 4 * 4], 2, 2);
+        let large = MemoryRGBA8Texture::from_memory(&[255; 4 * 16], 4, 4);
+        let unpadded = TextureOptions {
+            texture_padding: Some(0),
+            ..Default::default()
+        };
+        packer.pack_ref_with("small", &small, unpadded).unwrap();
+        assert!(!packer.can_pack(&large));
+
+        packer.remove(&"small").unwrap();
+        assert!(packer.can_pack(&large));
+    }
+
+    #[test]
+    fn compact_returns_moved_frames() {
+        let config = TexturePackerConfig {
+            max_width: 8,
+            max_height: 4,
+            allow_rotation: false,
+            texture_padding: 0,
+            trim: false,
+            ..Default::default()
+        };
+        let mut packer = TexturePacker::new_maxrects(config, MaxRectsHeuristic::default());
+        let texture = MemoryRGBA8Texture::from_memory(&[255; 4 * 16], 4, 4);
+        packer.pack_ref("a", &texture).unwrap();
+        packer.pack_ref("b", &texture).unwrap();
+        packer.remove(&"a");
+
+        let moves = packer.compact(SortHeuristic::Area).unwrap();
+        assert_eq!(
+            moves,
+            vec![FrameMove {
+                key: "b",
+                from: Rect::new(4, 0, 4, 4),
+                to: Rect::new(0, 0, 4, 4),
+                rotated: false,
+            }]
+        );
+        assert_eq!(packer.get_frame(&"b").unwrap().frame, Rect::new(0, 0, 4, 4));
+        assert!(packer.compact(SortHeuristic::Area).unwrap().is_empty());
+    }
+
+    #[test]
+    fn compact_breaks_ties_by_insertion_order() {
+        let config = TexturePackerConfig {
+            max_width: 16,
+            max_height: 4,
+            allow_rotation: false,
+            texture_padding: 0,
+            trim: false,
+            ..Default::default()
+        };
+        let mut packer = TexturePacker::new_maxrects(config, MaxRectsHeuristic::default());
+        let texture = MemoryRGBA8Texture::from_memory(&[255; 4 * 16], 4, 4);
+        for key in ["a", "b", "c", "d"] {
+            packer.pack_ref(key, &texture).unwrap();
+        }
+        packer.remove(&"a");
+
+        let layout = |packer: &TexturePacker<_, &str>| {
+            ["b", "c", "d"].map(|key| packer.get_frame(&key).unwrap().frame)
+        };
+        packer.compact(SortHeuristic::None).unwrap();
+        let first = layout(&packer);
+        assert_eq!(first[0], Rect::new(0, 0, 4, 4));
+        assert_eq!(first[1], Rect::new(4, 0, 4, 4));
+        assert_eq!(first[2], Rect::new(8, 0, 4, 4));
+        packer.compact(SortHeuristic::None).unwrap();
+        assert_eq!(layout(&packer), first);
+    }
+
+    #[test]
+    fn pack_into_existing_atlas() {
+        use image::{Rgba, RgbaImage};
+
+        let config = TexturePackerConfig {
+            max_width: 8,
+            max_height: 4,
+            allow_rotation: false,
+            texture_padding: 0,
+            trim: false,
+            ..Default::default()
+        };
+        let red = Rgba([255, 0, 0, 255]);
+        let blue = Rgba([0, 0, 255, 255]);
+        let base = RgbaImage::from_pixel(2, 4, red);
+        let texture = RgbaImage::from_pixel(4, 4, blue);
+
+        let mut packer = TexturePacker::new_maxrects(config, MaxRectsHeuristic::BottomLeftRule);
+        packer.set_base_ref(&base);
+        packer
+            .add_frame(Frame {
+                key: "old",
+                frame: Rect::new(0, 0, 2, 4),
+                rotated: false,
+                trimmed: false,
+                source: Rect::new(0, 0, 2, 4),
+            })
+            .unwrap();
+        packer.reserve(Rect::new(2, 0, 1, 1)).unwrap();
+        packer.pack_ref("new", &texture).unwrap();
+
+        assert_eq!(
+            packer.get_frame(&"new").unwrap().frame,
+            Rect::new(3, 0, 4, 4)
+        );
+        assert_eq!((packer.width(), packer.height()), (7, 4));
+        assert_eq!(packer.get(1, 3), Some(red));
+        assert_eq!(packer.get(2, 0), None);
+        assert_eq!(packer.get(3, 0), Some(blue));
+        assert_eq!(
+            packer.reserve(Rect::new(7, 0, 2, 1)),
+            Err(PackError::OutOfBounds)
+        );
+    }
+
+    #[test]
+    fn reserved_areas_survive_a_new_layout() {
+        let config = TexturePackerConfig {
+            max_width: 4,
+            max_height: 4,
+            allow_rotation: false,
+            texture_padding: 0,
+            trim: false,
+            ..Default::default()
+        };
+        let texture = MemoryRGBA8Texture::from_memory(&[255; 4 * 4], 2, 2);
+
+        let mut packer = TexturePacker::new_skyline(config);
+        packer.reserve(Rect::new(0, 0, 2, 1)).unwrap();
+        packer.pack_ref("a", &texture).unwrap();
+        assert_eq!(packer.get_frame(&"a").unwrap().frame, Rect::new(2, 0, 2, 2));
+
+        packer
+            .pack_smallest_ref(vec![], SortHeuristic::Area)
+            .unwrap();
+        let frame = packer.get_frame(&"a").unwrap().frame;
+        assert!(frame.x >= 2 || frame.y >= 1);
+
+        let mut packer: TexturePacker<MemoryRGBA8Texture, &str> =
+            TexturePacker::new_shelf(config, ShelfHeuristic::default());
+        assert_eq!(
+            packer.reserve(Rect::new(0, 0, 1, 1)),
+            Err(PackError::ReservationUnsupported)
+        );
+    }
+
+    #[test]
+    fn pinned_textures_stay_in_place() {
+        let config = TexturePackerConfig {
+            max_width: 4,
+            max_height: 4,
+            allow_rotation: false,
+            texture_padding: 0,
+            trim: false,
+            ..Default::default()
+        };
+        let white = MemoryRGBA8Texture::from_memory(&[255; 4], 1, 1);
+        let texture = MemoryRGBA8Texture::from_memory(&[255; 4 * 4], 2, 2);
+
+        let mut packer = TexturePacker::new_maxrects(config, MaxRectsHeuristic::BottomLeftRule);
+        packer.pin_ref("white", &white, 0, 0).unwrap();
+        assert_eq!(
+            packer.pin_ref("overlap", &texture, 0, 0),
+            Err(PackError::Overlapping)
+        );
+        assert_eq!(
+            packer.pin_ref("outside", &texture, 3, 0),
+            Err(PackError::OutOfBounds)
+        );
+
+        packer.pack_ref("a", &texture).unwrap();
+        packer.pack_ref("b", &texture).unwrap();
+        packer.remove(&"a");
+        packer.compact(SortHeuristic::Area).unwrap();
+        packer
+            .pack_smallest_ref(vec![], SortHeuristic::Area)
+            .unwrap();
+
+        assert_eq!(
+            packer.get_frame(&"white").unwrap().frame,
+            Rect::new(0, 0, 1, 1)
+        );
+        let b = packer.get_frame(&"b").unwrap().frame;
+        assert!(!overlaps(&b, &Rect::new(0, 0, 1, 1)));
+    }
+
+    #[test]
+    fn alpha_bleed_stays_within_frame() {
+        let config = TexturePackerConfig {
+            allow_rotation: false,
+            texture_padding: 0,
+            trim: false,
+            alpha_bleed: 2,
+            ..Default::default()
+        };
+        let mut packer = TexturePacker::new_skyline(config);
+        let red = [255, 0, 0, 255];
+        let clear = [0; 4];
+        let pixels = [red, clear, clear, clear].concat();
+        let faded = MemoryRGBA8Texture::from_memory(&pixels, 4, 1);
+        let green = MemoryRGBA8Texture::from_memory(&[0, 255, 0, 255], 1, 1);
+        packer.pack_own("faded", faded).unwrap();
+        packer.pack_own("green", green).unwrap();
+
+        let frame = packer.get_frame(&"faded").unwrap().frame;
+        let pixel = |dx| {
+            let p = packer.get(frame.x + dx, frame.y).unwrap();
+            (p.r, p.g, p.b, p.a)
+        };
+        assert_eq!(pixel(1), (255, 0, 0, 0));
+        assert_eq!(pixel(2), (255, 0, 0, 0));
+        // the green neighbour is closer but belongs to another frame
+        assert_eq!(pixel(3), (0, 0, 0, 0));
+    }
+
+    #[test]
+    fn alpha_bleed_fills_padding() {
+        let config = TexturePackerConfig {
+            allow_rotation: false,
+            texture_padding: 2,
+            trim: false,
+            alpha_bleed: 2,
+            ..Default::default()
+        };
+        let mut packer = TexturePacker::new_skyline(config);
+        let red = MemoryRGBA8Texture::from_memory(&[255, 0, 0, 255], 1, 1);
+        let green = MemoryRGBA8Texture::from_memory(&[0, 255, 0, 255], 1, 1);
+        packer.pack_own("red", red).unwrap();
+        packer.pack_own("green", green).unwrap();
+        assert_eq!(
+            packer.get_frame(&"green").unwrap().frame,
+            Rect::new(3, 0, 1, 1)
+        );
+
+        let pixel = |x, y| packer.get(x, y).map(|p| (p.r, p.g, p.b, p.a));
+        assert_eq!(pixel(1, 0), Some((255, 0, 0, 0)));
+        assert_eq!(pixel(2, 0), Some((255, 0, 0, 0)));
+        assert_eq!(pixel(0, 2), Some((255, 0, 0, 0)));
+        assert_eq!(pixel(4, 1), Some((0, 255, 0, 0)));
+        // farther than `alpha_bleed` from any pixel
+        assert_eq!(pixel(2, 2), None);
+    }
+
+    #[test]
+    fn extrusion_modes() {
+        let config = TexturePackerConfig {
+            allow_rotation: false,
+            border_padding: 1,
+            texture_padding: 0,
+            texture_extrusion: 1,
+            extrusion_mode: ExtrusionMode::Wrap,
+            trim: false,
+            ..Default::default()
+        };
+        let mut packer = TexturePacker::new_skyline(config);
+        let pixels = [[10, 0, 0, 255], [20, 0, 0, 255], [30, 0, 0, 255]].concat();
+        let tile = MemoryRGBA8Texture::from_memory(&pixels, 3, 1);
+        packer.pack_own("wrapped", tile.clone()).unwrap();
+        packer.pack_own("mirrored", tile).unwrap();
+        assert!(packer.set_extrusion_mode(&"mirrored", ExtrusionMode::Mirror));
+        assert!(!packer.set_extrusion_mode(&"missing", ExtrusionMode::Mirror));
+
+        let edges = |key| {
+            let frame = packer.get_frame(&key).unwrap().frame;
+            let red = |x| packer.get(x, frame.y).unwrap().r;
+            (red(frame.x - 1), red(frame.x + frame.w))
+        };
+        assert_eq!(edges("wrapped"), (30, 10));
+        assert_eq!(edges("mirrored"), (10, 30));
+    }
+
+    #[test]
+    fn texture_options_override_config() {
+        let config = TexturePackerConfig {
+            max_width: 16,
+            max_height: 16,
+            allow_rotation: true,
+            texture_padding: 0,
+            ..Default::default()
+        };
+        let mut packer = TexturePacker::new_maxrects(config, MaxRectsHeuristic::default());
+        let mut pixels = vec![0; 4 * 8];
+        pixels[4 * 4..].fill(255);
+        let panel = MemoryRGBA8Texture::from_memory(&pixels, 4, 2);
+        let options = TextureOptions {
+            texture_padding: Some(2),
+            trim: Some(false),
+            allow_rotation: Some(false),
+            ..Default::default()
+        };
+        packer.pack_own_with("panel", panel, options).unwrap();
+
+        let frame = packer.get_frame(&"panel").unwrap().clone();
+        assert!(!frame.trimmed && !frame.rotated);
+        assert_eq!((frame.frame.w, frame.frame.h), (4, 2));
+
+        // the padding of the panel is kept free, also after laying out anew
+        let tile = MemoryRGBA8Texture::from_memory(&[255; 4 * 4], 2, 2);
+        packer.pack_own("tile", tile).unwrap();
+        packer.compact(SortHeuristic::Area).unwrap();
+        let panel = packer.get_frame(&"panel").unwrap().frame;
+        let tile = packer.get_frame(&"tile").unwrap().frame;
+        let padded = Rect::new(panel.x, panel.y, panel.w + 2, panel.h + 2);
+        assert!(!overlaps(&padded, &tile));
+
+        packer.remove(&"panel");
+        assert!(packer.options.is_empty());
+    }
+
+    #[test]
+    fn trim_alpha_threshold() {
+        let mut pixels = [0, 0, 0, 2].repeat(9);
+        pixels[4 * 4..4 * 5].copy_from_slice(&[255; 4]);
+        let texture = MemoryRGBA8Texture::from_memory(&pixels, 3, 3);
+
+        let mut config = TexturePackerConfig::default();
+        assert_eq!(trimmed_rect(&config, &texture), Ok(Rect::new(0, 0, 3, 3)));
+        config.trim_alpha_threshold = 2;
+        assert_eq!(trimmed_rect(&config, &texture), Ok(Rect::new(1, 1, 1, 1)));
+
+        // the threshold is scaled to the range of the alpha channel
+        assert!(image::Rgba([0u16, 0, 0, 257 * 2]).is_transparent_within(2));
+        assert!(!image::Rgba([0u16, 0, 0, 257 * 2 + 1]).is_transparent_within(2));
+    }
+
+    #[test]
+    fn trim_modes() {
+        let mut pixels = vec![0; 4 * 8 * 4];
+        pixels[4 * 9..4 * 11].fill(255);
+        let texture = MemoryRGBA8Texture::from_memory(&pixels, 8, 4);
+        let trim = |trim_mode| {
+            let config = TexturePackerConfig {
+                trim_mode,
+                ..Default::default()
+            };
+            trimmed_rect(&config, &texture).unwrap()
+        };
+
+        assert_eq!(trim(TrimMode::Independent), Rect::new(1, 1, 2, 1));
+        assert_eq!(trim(TrimMode::Symmetric), Rect::new(1, 1, 6, 2));
+        // a pivot at the bottom center stays there
+        let feet = TrimMode::KeepPivot { x: 0.5, y: 1.0 };
+        assert_eq!(trim(feet), Rect::new(1, 1, 6, 3));
+    }
+
+    #[test]
+    fn common_trim_mode() {
+        let dot = |x: usize, y: usize| {
+            let mut pixels = vec![0; 4 * 16];
+            pixels[4 * (y * 4 + x)..4 * (y * 4 + x + 1)].fill(255);
+            MemoryRGBA8Texture::from_memory(&pixels, 4, 4)
+        };
+        let blank = MemoryRGBA8Texture::from_memory(&[0; 4 * 16], 4, 4);
+        let config = TexturePackerConfig {
+            trim_mode: TrimMode::Common,
+            ..Default::default()
+        };
+        let mut packer = TexturePacker::new_skyline(config);
+        let frames = vec![("a", dot(0, 1)), ("b", dot(2, 2)), ("c", blank)];
+        packer.pack_all_own(frames, SortHeuristic::None).unwrap();
+
+        for key in ["a", "b", "c"] {
+            let frame = packer.get_frame(&key).unwrap();
+            assert_eq!(frame.source, Rect::new(0, 1, 4, 4));
+            assert_eq!((frame.frame.w, frame.frame.h), (3, 2));
+        }
+    }
+
+    #[test]
+    fn custom_packers_may_stay_on_one_thread() {
+        use std::cell::Cell;
+
+        // `Rc` is neither `Send` nor `Sync`
+        struct Counting(SkylinePacker, Rc<Cell<u32>>);
+        impl Packer<u8> for Counting {
+            fn pack(&mut self, key: u8, rect: &Rect) -> Option<Frame<u8>> {
+                self.1.set(self.1.get() + 1);
+                self.0.pack(key, rect)
+            }
+            fn can_pack(&self, rect: &Rect) -> bool {
+                Packer::<u8>::can_pack(&self.0, rect)
+            }
+        }
+
+        let packed = Rc::new(Cell::new(0));
+        let counter = packed.clone();
+        let mut packer =
+            TexturePacker::with_packer(TexturePackerConfig::default(), move |config| {
+                Counting(SkylinePacker::new(config), counter.clone())
+            });
+        let texture = MemoryRGBA8Texture::from_memory(&[255; 4 * 4], 2, 2);
+        packer.pack_ref(0, &texture).unwrap();
+        packer.pack_ref(1, &texture).unwrap();
+        assert_eq!(packed.get(), 2);
+    }
+
+    #[test]
+    fn options_need_packer_support() {
+        // a custom packer which only knows the settings it was created with
+        struct Plain(SkylinePacker);
+        impl Packer<&'static str> for Plain {
+            fn pack(&mut self, key: &'static str, rect: &Rect) -> Option<Frame<&'static str>> {
+                self.0.pack(key, rect)
+            }
+            fn can_pack(&self, rect: &Rect) -> bool {
+                Packer::<&str>::can_pack(&self.0, rect)
+            }
+        }
+
+        let config = TexturePackerConfig::default();
+        let mut packer =
+            TexturePacker::with_packer(config, |config| Plain(SkylinePacker::new(config)));
+        let texture = MemoryRGBA8Texture::from_memory(&[255; 4 * 4], 2, 2);
+
+        let untrimmed = TextureOptions {
+            trim: Some(false),
+            ..Default::default()
+        };
+        packer
+            .pack_own_with("untrimmed", texture.clone(), untrimmed)
+            .unwrap();
+        let padded = TextureOptions {
+            texture_padding: Some(8),
+            ..Default::default()
+        };
+        let result = packer.pack_own_with("padded", texture, padded);
+        assert_eq!(result, Err(PackError::TextureOptionsUnsupported));
     }
 }
@@ -1,5 +1,112 @@
+use crate::rect::Rect;
+use std::cmp::{max, min};
 use std::default::Default;
 
+/// How the extrusion around a frame is filled, matching the address mode the atlas is sampled
+/// with.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum ExtrusionMode {
+    /// Repeat the pixels on the edge of the image.
+    #[default]
+    Clamp,
+    /// Continue with the pixels on the opposite edge, for textures which tile.
+    Wrap,
+    /// Continue with the image flipped at the edge.
+    Mirror,
+}
+
+impl ExtrusionMode {
+    // position within `0..size` of the pixel shown at `offset` from the start of the image
+    pub(crate) fn position(self, offset: i64, size: u32) -> u32 {
+        let size = i64::from(size.max(1));
+        let position = match self {
+            ExtrusionMode::Clamp => offset.clamp(0, size - 1),
+            ExtrusionMode::Wrap => offset.rem_euclid(size),
+            ExtrusionMode::Mirror => {
+                let position = offset.rem_euclid(2 * size);
+                if position < size {
+                    position
+                } else {
+                    2 * size - 1 - position
+                }
+            }
+        };
+        position as u32
+    }
+}
+
+/// How the empty pixels around an image are cut when trimming. The cut off pixels are recorded in
+/// [Frame::source](crate::Frame::source).
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum TrimMode {
+    /// Cut each side as far as possible.
+    #[default]
+    Independent,
+    /// Cut the same number of pixels from opposite sides, keeping the image centered.
+    Symmetric,
+    /// Cut so that the pivot at (`x`, `y`), given as fractions of the width and height of the
+    /// image, stays at about the same fractions of the trimmed image.
+    KeepPivot {
+        /// Horizontal position of the pivot, from `0.0` at the left to `1.0` at the right edge.
+        x: f32,
+        /// Vertical position of the pivot, from `0.0` at the top to `1.0` at the bottom edge.
+        y: f32,
+    },
+    /// Cut all images packed by one call, e.g. of
+    /// [pack_all_own](crate::TexturePacker::pack_all_own), to the bounding box of all of them,
+    /// so that animation frames keep lining up. Images without any pixels left take the
+    /// bounding box as well. Images packed one at a time are trimmed like
+    /// [Independent](Self::Independent).
+    Common,
+}
+
+impl TrimMode {
+    // widen `trimmed`, the smallest rect holding the pixels of a `w` x `h` image, as required
+    pub(crate) fn apply(self, trimmed: Rect, w: u32, h: u32) -> Rect {
+        let (x1, y1) = (trimmed.x, trimmed.y);
+        let (x2, y2) = (trimmed.x + trimmed.w, trimmed.y + trimmed.h);
+        let ((x, w), (y, h)) = match self {
+            TrimMode::Independent | TrimMode::Common => return trimmed,
+            TrimMode::Symmetric => (symmetric(x1, x2, w), symmetric(y1, y2, h)),
+            TrimMode::KeepPivot { x, y } => (keep_pivot(x, x1, x2, w), keep_pivot(y, y1, y2, h)),
+        };
+        Rect::new(x, y, w, h)
+    }
+}
+
+// the start and length of the range of `0..size` holding `start..end` which is cut equally on
+// both ends
+fn symmetric(start: u32, end: u32, size: u32) -> (u32, u32) {
+    let cut = min(start, size - end);
+    (cut, size - 2 * cut)
+}
+
+// the start and length of the range of `0..size` holding `start..end` which has `pivot`, a
+// fraction of `size`, at about the same fraction of its length
+fn keep_pivot(pivot: f32, start: u32, end: u32, size: u32) -> (u32, u32) {
+    let pivot = f64::from(pivot.clamp(0.0, 1.0));
+    let at = pivot * f64::from(size);
+    let before = if pivot > 0.0 {
+        (at - f64::from(start)) / pivot
+    } else {
+        0.0
+    };
+    let after = if pivot < 1.0 {
+        (f64::from(end) - at) / (1.0 - pivot)
+    } else {
+        0.0
+    };
+    let len = before.max(after).ceil().min(f64::from(size)) as u32;
+    let first = min(
+        (at - pivot * f64::from(len)).round().max(0.0) as u32,
+        size - len,
+    );
+
+    // rounding must not cut into the pixels
+    let first = min(first, start);
+    (first, max(len, end - first))
+}
+
 /// Configuration for a texture packer.
 #[derive(Debug, Copy, Clone)]
 pub struct TexturePackerConfig {
@@ -18,6 +125,20 @@ pub struct TexturePackerConfig {
     /// leaving potentially much unused space on the texture.
     pub force_max_dimensions: bool,
 
+    /// True to round the size of the output texture up to a power of two. Takes precedence over
+    /// [size_multiple](Self::size_multiple). Default value is `false`.
+    ///
+    /// Textures are only packed into the largest allowed size within
+    /// [max_width](Self::max_width) and [max_height](Self::max_height), e.g. 512 x 512 pixels
+    /// for a maximum of 600 x 600 pixels, [border_padding](Self::border_padding) included. The
+    /// same holds for [square](Self::square) and [size_multiple](Self::size_multiple).
+    pub power_of_two: bool,
+    /// True to make the output texture square. Default value is `false`.
+    pub square: bool,
+    /// Round the size of the output texture up to a multiple of this value, e.g. `4` for block
+    /// compressed formats. Default value is `1`.
+    pub size_multiple: u32,
+
     //
     // texture configuration
     //
@@ -27,9 +148,23 @@ pub struct TexturePackerConfig {
     pub texture_padding: u32,
     /// Size of the repeated pixels at the border of each image. Default value is `0`.
     pub texture_extrusion: u32,
+    /// How the pixels of the extrusion are taken from the image. Default value is
+    /// [ExtrusionMode::Clamp].
+    pub extrusion_mode: ExtrusionMode,
 
     /// True to trim the empty pixels of the input images. Default value is `true`.
     pub trim: bool,
+    /// How the empty pixels are cut when trimming. Default value is [TrimMode::Independent].
+    pub trim_mode: TrimMode,
+    /// Pixels whose alpha is at most this value, on a scale of `0` to `255`, count as empty when
+    /// trimming, e.g. to cut faint noise left by anti-aliasing. Default value is `0`.
+    pub trim_alpha_threshold: u8,
+
+    /// Distance in pixels up to which the fully transparent pixels of a frame, its extrusion and
+    /// the padding to its right and below take the color of the nearest non-transparent pixel of
+    /// the same frame, keeping their alpha at zero. Avoids dark fringes when the atlas is sampled
+    /// with filtering. Default value is `0`, which disables alpha bleeding.
+    pub alpha_bleed: u32,
 
     /// True to draw the red line on the edge of the each frames. Useful for debugging. Default
     /// value is `false`.
@@ -44,13 +179,139 @@ impl Default for TexturePackerConfig {
             allow_rotation: true,
 
             force_max_dimensions: false,
+            power_of_two: false,
+            square: false,
+            size_multiple: 1,
             border_padding: 0,
             texture_padding: 2,
             texture_extrusion: 0,
+            extrusion_mode: ExtrusionMode::Clamp,
 
             trim: true,
+            trim_mode: TrimMode::Independent,
+            trim_alpha_threshold: 0,
+            alpha_bleed: 0,
 
             texture_outlines: false,
         }
     }
 }
+
+impl TexturePackerConfig {
+    // size of the output texture whose frames cover `used_width` x `used_height` pixels
+    pub(crate) fn atlas_size(&self, used_width: u32, used_height: u32) -> (u32, u32) {
+        if self.force_max_dimensions {
+            let border = self.counted_border();
+            return (self.max_width + border, self.max_height + border);
+        }
+
+        let width = self.round_dimension(used_width);
+        let height = self.round_dimension(used_height);
+        if self.square {
+            (width.max(height), width.max(height))
+        } else {
+            (width, height)
+        }
+    }
+
+    // round a dimension of the output texture up as configured
+    fn round_dimension(&self, size: u32) -> u32 {
+        if size == 0 {
+            0
+        } else if self.power_of_two {
+            size.next_power_of_two()
+        } else {
+            let multiple = self.size_multiple.max(1);
+            size.div_ceil(multiple) * multiple
+        }
+    }
+
+    // largest dimension of the output texture allowed by the configuration, up to `max`
+    fn largest_dimension(&self, max: u32) -> u32 {
+        if max == 0 {
+            0
+        } else if self.power_of_two {
+            1 << (31 - max.leading_zeros())
+        } else {
+            let multiple = self.size_multiple.max(1);
+            max / multiple * multiple
+        }
+    }
+
+    // border pixels on both sides that count towards `max_width` and `max_height`; only a
+    // rounded output texture is kept within them, border included
+    fn counted_border(&self) -> u32 {
+        if self.power_of_two || self.square || self.size_multiple > 1 {
+            self.border_padding * 2
+        } else {
+            0
+        }
+    }
+
+    // the configuration of a packer for an output texture of at most `width` x `height` pixels
+    pub(crate) fn sized(&self, width: u32, height: u32) -> TexturePackerConfig {
+        let border = self.counted_border();
+        TexturePackerConfig {
+            max_width: width.saturating_sub(border),
+            max_height: height.saturating_sub(border),
+            ..*self
+        }
+    }
+
+    // the configuration with `max_width` and `max_height` lowered to the area left for packing
+    // in the largest allowed output texture, so that rounding it up never exceeds them
+    pub(crate) fn constrained(&self) -> TexturePackerConfig {
+        let (width, height) = self.largest_size();
+        self.sized(width, height)
+    }
+
+    // largest size of the output texture allowed by the configuration
+    pub(crate) fn largest_size(&self) -> (u32, u32) {
+        let width = self.largest_dimension(self.max_width);
+        let height = self.largest_dimension(self.max_height);
+        if self.square {
+            (width.min(height), width.min(height))
+        } else {
+            (width, height)
+        }
+    }
+}
+
+/// Settings of a single texture overriding those of the [TexturePackerConfig], see
+/// [TexturePacker::pack_own_with](crate::TexturePacker::pack_own_with). Every setting left at
+/// `None` is taken from the configuration.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct TextureOptions {
+    /// Overrides [TexturePackerConfig::texture_padding].
+    pub texture_padding: Option<u32>,
+    /// Overrides [TexturePackerConfig::texture_extrusion].
+    pub texture_extrusion: Option<u32>,
+    /// Overrides [TexturePackerConfig::extrusion_mode].
+    pub extrusion_mode: Option<ExtrusionMode>,
+    /// Overrides [TexturePackerConfig::trim].
+    pub trim: Option<bool>,
+    /// Overrides [TexturePackerConfig::allow_rotation].
+    pub allow_rotation: Option<bool>,
+}
+
+impl TextureOptions {
+    // `config` with the settings of these options
+    pub(crate) fn apply(&self, config: &TexturePackerConfig) -> TexturePackerConfig {
+        TexturePackerConfig {
+            texture_padding: self.texture_padding.unwrap_or(config.texture_padding),
+            texture_extrusion: self.texture_extrusion.unwrap_or(config.texture_extrusion),
+            extrusion_mode: self.extrusion_mode.unwrap_or(config.extrusion_mode),
+            trim: self.trim.unwrap_or(config.trim),
+            allow_rotation: self.allow_rotation.unwrap_or(config.allow_rotation),
+            ..*config
+        }
+    }
+
+    // true if a packer has to place the texture differently than with `config` alone
+    pub(crate) fn changes_layout(&self, config: &TexturePackerConfig) -> bool {
+        let own = self.apply(config);
+        own.texture_padding != config.texture_padding
+            || own.texture_extrusion != config.texture_extrusion
+            || own.allow_rotation != config.allow_rotation
+    }
+}